@@ -0,0 +1,125 @@
+// BSL 1.0 License
+
+//! Scoped autorelease pools.
+
+use crate::{ffi, Id, MessageTarget, Object, ObjectType};
+use core::{fmt, marker::PhantomData, ops::Deref};
+
+/// Run a closure inside of a new autorelease pool.
+///
+/// Objects that are autoreleased while the closure runs are released
+/// once it returns. The closure is given a reference to the pool, which
+/// can be used to bind autoreleased objects to the pool's lifetime.
+pub fn autoreleasepool<T, F: FnOnce(&AutoreleasePool) -> T>(f: F) -> T {
+    let pool = AutoreleasePool {
+        context: unsafe { ffi::objc_autoreleasePoolPush() },
+    };
+
+    f(&pool)
+}
+
+/// An autorelease pool that is currently active.
+///
+/// Created by [`autoreleasepool`], and popped once the closure that it
+/// was passed to returns.
+pub struct AutoreleasePool {
+    context: *mut (),
+}
+
+impl AutoreleasePool {
+    /// Bind an object that has been autoreleased to the lifetime of this
+    /// pool.
+    ///
+    /// This is useful for the results of messages that return
+    /// autoreleased objects, such as most getters.
+    ///
+    /// # Safety
+    ///
+    /// The object must be a valid instance of `T`, and it must have been
+    /// autoreleased while this pool was the innermost pool.
+    pub unsafe fn autoreleased<T: ObjectType>(&self, obj: T) -> Autoreleased<'_, T> {
+        Autoreleased {
+            obj,
+            _pool: PhantomData,
+        }
+    }
+}
+
+impl fmt::Debug for AutoreleasePool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("AutoreleasePool")
+            .field(&self.context)
+            .finish()
+    }
+}
+
+impl Drop for AutoreleasePool {
+    fn drop(&mut self) {
+        unsafe { ffi::objc_autoreleasePoolPop(self.context) };
+    }
+}
+
+/// An object that is kept alive by an autorelease pool.
+///
+/// The reference cannot outlive the pool that the object belongs to. Use
+/// [`Autoreleased::retain`] to keep the object alive after the pool is
+/// popped.
+#[repr(transparent)]
+pub struct Autoreleased<'p, T: ObjectType = Object> {
+    obj: T,
+    _pool: PhantomData<&'p AutoreleasePool>,
+}
+
+impl<'p, T: ObjectType> Autoreleased<'p, T> {
+    /// Get the pointer associated with this object.
+    pub fn as_ptr(&self) -> *const () {
+        self.obj.as_object().as_ptr()
+    }
+
+    /// Retain the object, so that it outlives the autorelease pool.
+    pub fn retain(self) -> Id<T> {
+        unsafe { Id::retain(self.obj) }
+    }
+}
+
+impl<T: ObjectType> Id<T> {
+    /// Autorelease this object, handing ownership over to the given pool.
+    ///
+    /// # Safety
+    ///
+    /// The runtime always hands the object over to the innermost pool, so
+    /// `pool` must be the innermost pool on the current thread.
+    pub unsafe fn autorelease(this: Id<T>, pool: &AutoreleasePool) -> Autoreleased<'_, T> {
+        let obj = Id::into_raw(this);
+        ffi::objc_autorelease(obj.as_object().as_ptr());
+        pool.autoreleased(obj)
+    }
+}
+
+impl<'p, T: ObjectType> Clone for Autoreleased<'p, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'p, T: ObjectType> Copy for Autoreleased<'p, T> {}
+
+impl<'p, T: ObjectType> Deref for Autoreleased<'p, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.obj
+    }
+}
+
+impl<'p, T: ObjectType> fmt::Debug for Autoreleased<'p, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Autoreleased").field(&self.as_ptr()).finish()
+    }
+}
+
+unsafe impl<'p, T: ObjectType> MessageTarget for Autoreleased<'p, T> {
+    fn ptr(self) -> *mut () {
+        self.as_ptr() as _
+    }
+}
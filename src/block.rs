@@ -0,0 +1,429 @@
+// BSL 1.0 License
+
+//! Types for representing the Objective C block construct.
+//!
+//! Blocks are laid out according to the [Clang Block ABI]. A
+//! [`StackBlock`] is a block that lives on the Rust stack, while an
+//! [`RcBlock`] is a reference-counted block that lives on the heap. Both
+//! dereference to a [`Block`], which can also refer to blocks received
+//! from Objective C.
+//!
+//! Blocks created from Rust closures carry their type encoding, so the
+//! closure's argument and return types must implement [`Encode`].
+//!
+//! [Clang Block ABI]: https://clang.llvm.org/docs/Block-ABI-Apple.html
+
+use crate::{
+    cstr::c_char,
+    encode::{Encode, EncodeArguments, StaticEncoding},
+    ffi,
+    message::Sealed,
+    Imp, MessageTarget, Result,
+};
+use core::{
+    fmt,
+    marker::PhantomData,
+    mem,
+    ops::Deref,
+    ptr::{self, NonNull},
+};
+use libc::{c_int, c_ulong};
+
+/// The block has copy and dispose helpers in its descriptor.
+const BLOCK_HAS_COPY_DISPOSE: c_int = 1 << 25;
+
+/// The block has a type encoding in its descriptor.
+const BLOCK_HAS_SIGNATURE: c_int = 1 << 30;
+
+/// The header that every block starts with.
+#[repr(C)]
+pub(crate) struct BlockLiteral {
+    isa: *const (),
+    flags: c_int,
+    reserved: c_int,
+    invoke: unsafe extern "C" fn(),
+    descriptor: *const BlockDescriptor,
+}
+
+/// The descriptor describing the size and helpers of a block.
+#[repr(C)]
+struct BlockDescriptor {
+    reserved: c_ulong,
+    size: c_ulong,
+    copy: unsafe extern "C" fn(*mut (), *const ()),
+    dispose: unsafe extern "C" fn(*mut ()),
+    signature: *const c_char,
+}
+
+/// Arguments that can be passed into a block.
+///
+/// # Safety
+///
+/// This should only really be implemented for tuple types. It has
+/// a `Sealed` marker for this reason.
+pub unsafe trait BlockArguments: Sealed + Sized {
+    /// Call the given block with this as an argument.
+    ///
+    /// # Safety
+    ///
+    /// The parameters must be valid for the given block.
+    unsafe fn call_block<Return>(self, block: *const ()) -> Return;
+}
+
+macro_rules! block_arguments {
+    ($($ident: ident)*) => {
+        #[allow(non_snake_case, unused_parens)]
+        unsafe impl<$($ident),*> BlockArguments for ($($ident,)*) {
+            #[inline]
+            unsafe fn call_block<Return>(self, block: *const ()) -> Return {
+                // transmute the invoke function to its real signature
+                let invoke = (*(block as *const BlockLiteral)).invoke;
+                let function_ptr: unsafe extern "C" fn(*const (), $($ident),*) -> Return =
+                    mem::transmute(invoke);
+
+                // disassemble the tuple and call the function
+                let ($($ident,)*) = self;
+                function_ptr(block, $($ident),*)
+            }
+        }
+    };
+}
+
+/// A closure that can be used as the body of a block.
+///
+/// This is implemented for all closures taking up to twelve arguments,
+/// where `Args` is a tuple of the closure's argument types.
+///
+/// # Safety
+///
+/// `invoke` must return a function that can be used as the `invoke`
+/// function of a [`StackBlock`] containing this closure.
+pub unsafe trait BlockClosure<Args>: Sized {
+    /// The return type of the closure.
+    type Output;
+
+    /// Get the function used to invoke a block containing this closure.
+    fn invoke() -> unsafe extern "C" fn();
+}
+
+macro_rules! block_closure {
+    ($($ident: ident)*) => {
+        #[allow(non_snake_case, unused_parens)]
+        unsafe impl<Func: Fn($($ident),*) -> Ret, Ret, $($ident),*> BlockClosure<($($ident,)*)>
+            for Func
+        {
+            type Output = Ret;
+
+            fn invoke() -> unsafe extern "C" fn() {
+                unsafe extern "C" fn invoke<Func: Fn($($ident),*) -> Ret, Ret, $($ident),*>(
+                    block: *mut BlockLiteral,
+                    $($ident: $ident),*
+                ) -> Ret {
+                    let block = &*(block as *const StackBlock<($($ident,)*), Ret, Func>);
+                    (block.closure)($($ident),*)
+                }
+
+                let invoke: unsafe extern "C" fn(*mut BlockLiteral, $($ident),*) -> Ret =
+                    invoke::<Func, Ret, $($ident),*>;
+                unsafe { mem::transmute(invoke) }
+            }
+        }
+    };
+}
+
+macro_rules! block_closures {
+    () => {
+        block_arguments! {}
+        block_closure! {}
+    };
+    ($head: ident $($ident: ident)*) => {
+        block_arguments! { $head $($ident)* }
+        block_closure! { $head $($ident)* }
+        block_closures! { $($ident)* }
+    };
+}
+
+block_closures! {
+    A B C D E F G H I J K L
+}
+
+/// A reference to an Objective C block.
+///
+/// `A` is a tuple of the block's argument types, and `R` is its return
+/// type. This type is only ever used behind a reference.
+#[repr(C)]
+pub struct Block<A, R> {
+    literal: BlockLiteral,
+    _marker: PhantomData<fn(A) -> R>,
+}
+
+impl<A: BlockArguments, R> Block<A, R> {
+    /// Create a reference to a block from a raw pointer.
+    ///
+    /// # Safety
+    ///
+    /// The pointer must be a valid pointer to a block that takes the
+    /// arguments `A` and returns `R`, and it must stay alive for `'a`.
+    pub unsafe fn from_ptr<'a>(ptr: *const ()) -> &'a Block<A, R> {
+        &*(ptr as *const Block<A, R>)
+    }
+
+    /// Get the pointer to this block.
+    pub fn as_ptr(&self) -> *const () {
+        self as *const Self as *const ()
+    }
+
+    /// Invoke the block with the given arguments.
+    ///
+    /// Returns an error if the block raises an Objective C exception.
+    pub fn call(&self, arguments: A) -> Result<R> {
+        let block = self.as_ptr();
+        unsafe { crate::error::error_catcher(move || arguments.call_block(block)) }
+    }
+
+    /// Copy this block, producing a reference-counted block.
+    ///
+    /// Stack blocks are copied to the heap, while blocks that are already
+    /// on the heap have their reference count incremented.
+    ///
+    /// # Safety
+    ///
+    /// The copy has no lifetime, so anything the block captures must
+    /// outlive it. For a [`StackBlock`], use [`StackBlock::copy`] instead,
+    /// which checks this for the closure.
+    pub unsafe fn copy(&self) -> RcBlock<A, R> {
+        RcBlock::from_copied(ffi::_Block_copy(self.as_ptr()))
+    }
+
+    /// Create a method implementation that calls this block.
+    ///
+    /// The block is called with the receiver of the method followed by the
+    /// method's arguments; the selector is not passed. The block is
+    /// copied, and is only released once the implementation is removed
+    /// with [`remove_block_imp`].
+    ///
+    /// # Safety
+    ///
+    /// Anything the block captures must outlive the implementation, as
+    /// with [`Block::copy`].
+    pub unsafe fn to_imp(&self) -> Imp {
+        ffi::imp_implementationWithBlock(self.as_ptr())
+    }
+}
+
+/// Release the block backing a method implementation that was created
+/// with [`Block::to_imp`].
+///
+/// Returns `false` if the implementation was not created from a block.
+///
+/// # Safety
+///
+/// The implementation must not be used by any method, or be called
+/// afterwards.
+pub unsafe fn remove_block_imp(imp: Imp) -> bool {
+    ffi::imp_removeBlock(imp).as_bool()
+}
+
+impl<A, R> fmt::Debug for Block<A, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Block")
+            .field(&(self as *const Self as *const ()))
+            .finish()
+    }
+}
+
+unsafe impl<A, R> MessageTarget for &Block<A, R> {
+    fn ptr(self) -> *mut () {
+        self as *const Block<A, R> as *mut ()
+    }
+}
+
+/// A block that lives on the stack.
+///
+/// If the block is copied to the heap by Objective C code, the closure is
+/// cloned into the copy.
+#[repr(C)]
+pub struct StackBlock<A, R, F> {
+    literal: BlockLiteral,
+    closure: F,
+    _marker: PhantomData<fn(A) -> R>,
+}
+
+impl<A: EncodeArguments, R: Encode, F> StackBlock<A, R, F> {
+    /// The type encoding of the block, which is null if it is too long.
+    const SIGNATURE: &'static StaticEncoding = &StaticEncoding::block(&R::ENCODING, A::ENCODINGS);
+
+    /// Descriptor for blocks that are moved to the heap exactly once, and
+    /// never used from the stack afterwards.
+    const MOVE_DESCRIPTOR: &'static BlockDescriptor = &BlockDescriptor {
+        reserved: 0,
+        size: mem::size_of::<Self>() as c_ulong,
+        copy: Self::move_helper,
+        dispose: Self::dispose_helper,
+        signature: Self::SIGNATURE.as_ptr(),
+    };
+
+    fn with_descriptor(closure: F, descriptor: &'static BlockDescriptor) -> Self
+    where
+        F: BlockClosure<A, Output = R>,
+    {
+        let mut flags = BLOCK_HAS_COPY_DISPOSE;
+        if !descriptor.signature.is_null() {
+            flags |= BLOCK_HAS_SIGNATURE;
+        }
+
+        StackBlock {
+            literal: BlockLiteral {
+                isa: unsafe { ffi::_NSConcreteStackBlock.as_ptr() as *const () },
+                flags,
+                reserved: 0,
+                invoke: F::invoke(),
+                descriptor,
+            },
+            closure,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<A, R, F> StackBlock<A, R, F> {
+    /// Get the pointer to this block.
+    pub fn as_ptr(&self) -> *const () {
+        self as *const Self as *const ()
+    }
+
+    unsafe extern "C" fn move_helper(_dst: *mut (), _src: *const ()) {
+        // the runtime has already copied the bytes of the closure over,
+        // and the source is forgotten afterwards
+    }
+
+    unsafe extern "C" fn dispose_helper(block: *mut ()) {
+        let block = block as *mut Self;
+        ptr::drop_in_place(&mut (*block).closure);
+    }
+}
+
+impl<A: EncodeArguments, R: Encode, F: Clone> StackBlock<A, R, F> {
+    /// Descriptor for blocks that stay usable on the stack after they are
+    /// copied to the heap.
+    const CLONE_DESCRIPTOR: &'static BlockDescriptor = &BlockDescriptor {
+        reserved: 0,
+        size: mem::size_of::<Self>() as c_ulong,
+        copy: Self::clone_helper,
+        dispose: Self::dispose_helper,
+        signature: Self::SIGNATURE.as_ptr(),
+    };
+
+    /// Create a new block from a closure.
+    pub fn new(closure: F) -> Self
+    where
+        F: BlockClosure<A, Output = R>,
+    {
+        Self::with_descriptor(closure, Self::CLONE_DESCRIPTOR)
+    }
+
+    /// Copy this block to the heap.
+    pub fn copy(&self) -> RcBlock<A, R>
+    where
+        F: 'static,
+    {
+        unsafe { RcBlock::from_copied(ffi::_Block_copy(self.as_ptr())) }
+    }
+
+    unsafe extern "C" fn clone_helper(dst: *mut (), src: *const ()) {
+        // the runtime has copied the bytes of the closure over, but the
+        // original is still owned by the stack block
+        let dst = dst as *mut Self;
+        let src = &*(src as *const Self);
+        ptr::write(&mut (*dst).closure, src.closure.clone());
+    }
+}
+
+impl<A, R, F> Deref for StackBlock<A, R, F> {
+    type Target = Block<A, R>;
+
+    fn deref(&self) -> &Block<A, R> {
+        unsafe { &*(self as *const Self as *const Block<A, R>) }
+    }
+}
+
+impl<A, R, F> fmt::Debug for StackBlock<A, R, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("StackBlock").field(&self.as_ptr()).finish()
+    }
+}
+
+/// A reference-counted block that lives on the heap.
+pub struct RcBlock<A, R> {
+    ptr: NonNull<BlockLiteral>,
+    _marker: PhantomData<fn(A) -> R>,
+}
+
+impl<A, R> RcBlock<A, R> {
+    /// Create a new block on the heap from a closure.
+    pub fn new<F: BlockClosure<A, Output = R> + 'static>(closure: F) -> Self
+    where
+        A: EncodeArguments,
+        R: Encode,
+    {
+        let block = StackBlock::with_descriptor(closure, StackBlock::<A, R, F>::MOVE_DESCRIPTOR);
+        let ptr = unsafe { ffi::_Block_copy(block.as_ptr()) };
+
+        // the closure now belongs to the heap block
+        mem::forget(block);
+        unsafe { RcBlock::from_copied(ptr) }
+    }
+
+    unsafe fn from_copied(ptr: *mut ()) -> Self {
+        RcBlock {
+            ptr: NonNull::new(ptr as *mut BlockLiteral).expect("Failed to copy block"),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Get the pointer to this block.
+    pub fn as_ptr(&self) -> *const () {
+        self.ptr.as_ptr() as *const ()
+    }
+}
+
+impl<A, R> Deref for RcBlock<A, R> {
+    type Target = Block<A, R>;
+
+    fn deref(&self) -> &Block<A, R> {
+        unsafe { &*(self.ptr.as_ptr() as *const Block<A, R>) }
+    }
+}
+
+impl<A, R> Clone for RcBlock<A, R> {
+    fn clone(&self) -> Self {
+        unsafe { RcBlock::from_copied(ffi::_Block_copy(self.as_ptr())) }
+    }
+}
+
+impl<A, R> Drop for RcBlock<A, R> {
+    fn drop(&mut self) {
+        unsafe { ffi::_Block_release(self.as_ptr()) };
+    }
+}
+
+impl<A, R> fmt::Debug for RcBlock<A, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("RcBlock").field(&self.as_ptr()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cstr::CStr;
+
+    #[test]
+    fn blocks_carry_their_signature() {
+        let block = StackBlock::new(|a: i32, b: f64| -> u8 { (a as f64 + b) as u8 });
+        assert_ne!(block.literal.flags & BLOCK_HAS_SIGNATURE, 0);
+
+        let signature = unsafe { CStr::from_ptr((*block.literal.descriptor).signature) };
+        assert_eq!(signature.to_str(), Ok("C@?id"));
+    }
+}
@@ -0,0 +1,85 @@
+// BSL 1.0 License
+
+//! The Objective C `BOOL` type.
+
+use crate::{Encode, Encoding};
+use core::fmt;
+
+cfg_if::cfg_if! {
+    // these match the conditions under which Apple's runtime defines
+    // `OBJC_BOOL_IS_BOOL`
+    if #[cfg(all(
+        target_vendor = "apple",
+        any(
+            target_arch = "aarch64",
+            all(target_os = "ios", target_pointer_width = "64", not(target_abi = "macabi")),
+            all(target_os = "tvos", target_pointer_width = "64"),
+            target_os = "watchos",
+        ),
+    ))] {
+        // `BOOL` is a C `bool`, which has the same ABI as a `u8`
+        type Inner = u8;
+        const ENCODING: Encoding<'static> = Encoding::Bool;
+    } else if #[cfg(target_vendor = "apple")] {
+        type Inner = i8;
+        const ENCODING: Encoding<'static> = Encoding::Char;
+    } else {
+        type Inner = u8;
+        const ENCODING: Encoding<'static> = Encoding::UChar;
+    }
+}
+
+/// The Objective C `BOOL` type.
+///
+/// The representation of `BOOL` varies between platforms, and it may hold
+/// values other than `YES` and `NO`. Any non-zero value is treated as
+/// true.
+#[repr(transparent)]
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Bool {
+    value: Inner,
+}
+
+impl Bool {
+    /// The `YES` value.
+    pub const YES: Bool = Bool { value: 1 };
+
+    /// The `NO` value.
+    pub const NO: Bool = Bool { value: 0 };
+
+    /// Create a `Bool` from a Rust `bool`.
+    pub const fn new(value: bool) -> Bool {
+        if value {
+            Bool::YES
+        } else {
+            Bool::NO
+        }
+    }
+
+    /// Convert this `Bool` into a Rust `bool`.
+    pub const fn as_bool(self) -> bool {
+        self.value != 0
+    }
+}
+
+impl From<bool> for Bool {
+    fn from(value: bool) -> Bool {
+        Bool::new(value)
+    }
+}
+
+impl From<Bool> for bool {
+    fn from(value: Bool) -> bool {
+        value.as_bool()
+    }
+}
+
+impl fmt::Debug for Bool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(if self.as_bool() { "YES" } else { "NO" })
+    }
+}
+
+unsafe impl Encode for Bool {
+    const ENCODING: Encoding<'static> = ENCODING;
+}
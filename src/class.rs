@@ -1,13 +1,18 @@
 // BSL 1.0 License
 
-use crate::{cstr::CStr, ffi};
-use core::{fmt, ptr::NonNull, str};
+use crate::{cstr::CStr, ffi, List, Method, Sel};
+use alloc::vec::Vec;
+use core::{fmt, iter::FusedIterator, ptr::NonNull, str};
 
 opaque_type! {
     #[doc = "An Objective-C class."]
     Class, AtomicClass
 }
 
+// the runtime synchronizes access to classes itself
+unsafe impl Send for Class {}
+unsafe impl Sync for Class {}
+
 impl fmt::Debug for Class {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_tuple("Class").field(&self.name()).finish()
@@ -32,10 +37,130 @@ impl Class {
     }
 
     /// Get the superclass for this class.
-    pub fn superclass(&self) -> Class {
+    ///
+    /// Returns `None` if this is a root class.
+    pub fn superclass(&self) -> Option<Class> {
         let ptr = unsafe { ffi::class_getSuperclass(self.ptr.as_ptr()) };
-        Class {
-            ptr: NonNull::new(ptr as _).expect("Class pointer should never be null"),
+        Some(Class {
+            ptr: NonNull::new(ptr as _)?,
+        })
+    }
+
+    /// Get the metaclass of this class, which holds its class methods.
+    pub fn metaclass(&self) -> Class {
+        unsafe { Class::from_ptr(ffi::object_getClass(self.as_ptr())) }
+    }
+
+    /// Tell whether this class is a metaclass.
+    pub fn is_metaclass(&self) -> bool {
+        unsafe { ffi::class_isMetaClass(self.as_ptr()) }.as_bool()
+    }
+
+    /// Get the size of instances of this class, in bytes.
+    pub fn instance_size(&self) -> usize {
+        unsafe { ffi::class_getInstanceSize(self.as_ptr()) }
+    }
+
+    /// Get the version number of this class.
+    pub fn version(&self) -> i32 {
+        unsafe { ffi::class_getVersion(self.as_ptr()) }
+    }
+
+    /// Set the version number of this class.
+    pub fn set_version(&self, version: i32) {
+        unsafe { ffi::class_setVersion(self.as_ptr(), version) }
+    }
+
+    /// Tell whether instances of this class respond to the given selector.
+    pub fn responds_to(&self, sel: Sel) -> bool {
+        unsafe { ffi::class_respondsToSelector(self.as_ptr(), sel.as_ptr()) }.as_bool()
+    }
+
+    /// Iterate over the superclasses of this class, starting with its
+    /// direct superclass and ending with the root class.
+    pub fn ancestors(&self) -> Ancestors {
+        Ancestors {
+            next: self.superclass(),
+        }
+    }
+
+    /// Iterate over all of the classes registered with the runtime.
+    pub fn all() -> List<Class> {
+        let mut count = 0;
+        unsafe {
+            let classes = ffi::objc_copyClassList(&mut count);
+            List::from_raw(classes as *mut Class, count as usize)
         }
     }
+
+    /// Get the direct subclasses of this class.
+    pub fn subclasses(&self) -> Vec<Class> {
+        Class::all()
+            .filter(|cls| cls.superclass() == Some(*self))
+            .collect()
+    }
+
+    /// Get the instance method of this class with the given selector.
+    ///
+    /// This also searches the superclasses of this class.
+    pub fn instance_method(&self, sel: Sel) -> Option<Method> {
+        let ptr = unsafe { ffi::class_getInstanceMethod(self.as_ptr(), sel.as_ptr()) };
+        if ptr.is_null() {
+            None
+        } else {
+            Some(unsafe { Method::from_ptr(ptr) })
+        }
+    }
+
+    /// Get the class method of this class with the given selector.
+    ///
+    /// This also searches the superclasses of this class.
+    pub fn class_method(&self, sel: Sel) -> Option<Method> {
+        let ptr = unsafe { ffi::class_getClassMethod(self.as_ptr(), sel.as_ptr()) };
+        if ptr.is_null() {
+            None
+        } else {
+            Some(unsafe { Method::from_ptr(ptr) })
+        }
+    }
+
+    /// Iterate over the instance methods implemented by this class.
+    ///
+    /// Methods implemented by superclasses are not included.
+    pub fn instance_methods(&self) -> List<Method> {
+        unsafe { copy_method_list(self.as_ptr()) }
+    }
+
+    /// Iterate over the class methods implemented by this class.
+    ///
+    /// Methods implemented by superclasses are not included.
+    pub fn class_methods(&self) -> List<Method> {
+        unsafe { copy_method_list(self.metaclass().as_ptr()) }
+    }
+}
+
+/// An iterator over the superclasses of a class.
+///
+/// This is returned by [`Class::ancestors`].
+#[derive(Debug, Clone)]
+pub struct Ancestors {
+    next: Option<Class>,
+}
+
+impl Iterator for Ancestors {
+    type Item = Class;
+
+    fn next(&mut self) -> Option<Class> {
+        let cls = self.next?;
+        self.next = cls.superclass();
+        Some(cls)
+    }
+}
+
+impl FusedIterator for Ancestors {}
+
+unsafe fn copy_method_list(cls: *const ()) -> List<Method> {
+    let mut count = 0;
+    let methods = ffi::class_copyMethodList(cls, &mut count);
+    List::from_raw(methods as *mut Method, count as usize)
 }
@@ -1,3 +1,25 @@
 // BSL 1.0 License
 
-pub(crate) use cstr_core::{CStr, c_char};
\ No newline at end of file
+pub(crate) use cstr_core::{CStr, c_char};
+
+use core::str;
+
+/// Convert a string owned by the runtime into a `&str`, treating a null
+/// pointer as an empty string.
+///
+/// # Safety
+///
+/// The pointer must either be null or point to a nul-terminated string
+/// that lives for `'a`.
+///
+/// # Panics
+///
+/// Panics if the string is not valid UTF-8.
+pub(crate) unsafe fn runtime_str<'a>(c_ptr: *const c_char) -> &'a str {
+    if c_ptr.is_null() {
+        return "";
+    }
+
+    let c_str = CStr::from_ptr(c_ptr);
+    str::from_utf8(c_str.to_bytes()).expect("Runtime strings should be valid UTF-8")
+}
@@ -0,0 +1,618 @@
+// BSL 1.0 License
+
+//! Types for declaring new classes at runtime.
+//!
+//! A [`ClassDecl`] wraps a class that has been allocated with
+//! `objc_allocateClassPair` but not yet registered. Methods, instance
+//! variables and protocols can be added to it, and then it can be
+//! registered with the runtime to produce a [`Class`].
+//!
+//! A [`ProtocolDecl`] does the same for protocols, wrapping
+//! `objc_allocateProtocol` and producing a [`Protocol`].
+
+use crate::{
+    cstr::{c_char, CStr},
+    encode::{self, Encode, EncodeArguments, Encoding},
+    ffi, send_super_message, Block, BlockArguments, Bool, Class, Imp, MessageTarget,
+    PropertyAttributes, Protocol, RcBlock, Sel, SetterSemantics,
+};
+use alloc::{string::String, vec::Vec};
+use core::{fmt, mem, ptr};
+
+/// A function that can be used as the implementation of a method.
+///
+/// # Safety
+///
+/// The function must have the signature of an Objective C method; that
+/// is, it must take the receiver and the selector as its first two
+/// arguments.
+pub unsafe trait MethodImplementation: Copy {
+    /// The type encoding of the return type.
+    const RETURN: Encoding<'static>;
+
+    /// The type encodings of the arguments, including the receiver and
+    /// the selector.
+    const ARGUMENTS: &'static [Encoding<'static>];
+
+    /// Get the function as a type-erased function pointer.
+    fn imp(self) -> Imp;
+
+    /// Convert a type-erased function pointer back into this function
+    /// type.
+    ///
+    /// # Safety
+    ///
+    /// `imp` must have the signature of this function type.
+    unsafe fn from_imp(imp: Imp) -> Self;
+}
+
+macro_rules! method_implementation {
+    ($($ident: ident)*) => {
+        unsafe impl<Receiver, Return, $($ident),*> MethodImplementation
+            for extern "C" fn(Receiver, Sel, $($ident),*) -> Return
+        where
+            Receiver: MessageTarget + Encode,
+            Return: Encode,
+            $($ident: Encode),*
+        {
+            const RETURN: Encoding<'static> = Return::ENCODING;
+            const ARGUMENTS: &'static [Encoding<'static>] =
+                <(Receiver, Sel, $($ident,)*) as EncodeArguments>::ENCODINGS;
+
+            fn imp(self) -> Imp {
+                unsafe { mem::transmute(self) }
+            }
+
+            unsafe fn from_imp(imp: Imp) -> Self {
+                mem::transmute(imp)
+            }
+        }
+
+        unsafe impl<Receiver, Return, $($ident),*> MethodImplementation
+            for unsafe extern "C" fn(Receiver, Sel, $($ident),*) -> Return
+        where
+            Receiver: MessageTarget + Encode,
+            Return: Encode,
+            $($ident: Encode),*
+        {
+            const RETURN: Encoding<'static> = Return::ENCODING;
+            const ARGUMENTS: &'static [Encoding<'static>] =
+                <(Receiver, Sel, $($ident,)*) as EncodeArguments>::ENCODINGS;
+
+            fn imp(self) -> Imp {
+                unsafe { mem::transmute(self) }
+            }
+
+            unsafe fn from_imp(imp: Imp) -> Self {
+                mem::transmute(imp)
+            }
+        }
+    };
+}
+
+macro_rules! method_implementations {
+    () => {
+        method_implementation! {}
+    };
+    ($head: ident $($ident: ident)*) => {
+        method_implementation! { $head $($ident)* }
+        method_implementations! { $($ident)* }
+    };
+}
+
+method_implementations! {
+    A B C D E F G H I J K L M N O P Q R S T U V W X
+}
+
+/// A class that is being declared at runtime.
+///
+/// The class is disposed of if it is dropped without being registered.
+pub struct ClassDecl {
+    cls: Class,
+}
+
+impl fmt::Debug for ClassDecl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ClassDecl").field(&self.cls.name()).finish()
+    }
+}
+
+impl ClassDecl {
+    /// Start declaring a new class with the given name and superclass.
+    ///
+    /// Returns `None` if a class with the same name already exists.
+    pub fn new(name: &CStr, superclass: Class) -> Option<ClassDecl> {
+        Self::with_superclass(name, Some(superclass))
+    }
+
+    /// Start declaring a new root class with the given name.
+    ///
+    /// Returns `None` if a class with the same name already exists.
+    pub fn root(name: &CStr) -> Option<ClassDecl> {
+        Self::with_superclass(name, None)
+    }
+
+    fn with_superclass(name: &CStr, superclass: Option<Class>) -> Option<ClassDecl> {
+        let superclass = superclass.map_or(ptr::null(), |cls| cls.as_ptr());
+        let ptr = unsafe { ffi::objc_allocateClassPair(superclass, name.as_ptr(), 0) };
+        if ptr.is_null() {
+            return None;
+        }
+
+        Some(ClassDecl {
+            cls: unsafe { Class::from_ptr(ptr) },
+        })
+    }
+
+    /// Add an instance method to the class.
+    ///
+    /// The type encoding of the method is derived from the signature of
+    /// `imp`.
+    ///
+    /// # Safety
+    ///
+    /// The signature of `imp` must be compatible with `sel`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of arguments does not match the selector, or
+    /// if the class already has a method with this selector.
+    pub unsafe fn add_method<F: MethodImplementation>(&mut self, sel: Sel, imp: F) {
+        let success = add_method_to(self.cls, sel, imp);
+        assert!(success, "Failed to add method {:?}", sel);
+    }
+
+    /// Add a class method to the class.
+    ///
+    /// The type encoding of the method is derived from the signature of
+    /// `imp`.
+    ///
+    /// # Safety
+    ///
+    /// The signature of `imp` must be compatible with `sel`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of arguments does not match the selector, or
+    /// if the class already has a class method with this selector.
+    pub unsafe fn add_class_method<F: MethodImplementation>(&mut self, sel: Sel, imp: F) {
+        let success = add_method_to(self.cls.metaclass(), sel, imp);
+        assert!(success, "Failed to add method {:?}", sel);
+    }
+
+    /// Add an instance method to the class that is implemented by a block.
+    ///
+    /// The block takes the receiver followed by the method's arguments, and
+    /// the type encoding of the method is derived from its signature. The
+    /// block is copied into the method.
+    ///
+    /// # Safety
+    ///
+    /// The signature of `block` must be compatible with `sel`, and anything
+    /// the block captures must outlive the class, since the copy is never
+    /// released.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the block does not take a receiver, if the number of
+    /// arguments does not match the selector, or if the class already has
+    /// a method with this selector.
+    pub unsafe fn add_block_method<A, R>(&mut self, sel: Sel, block: &Block<A, R>)
+    where
+        A: BlockArguments + EncodeArguments,
+        R: Encode,
+    {
+        add_block_method_to(self.cls, sel, block)
+    }
+
+    /// Add a class method to the class that is implemented by a block.
+    ///
+    /// This works in the same way as [`ClassDecl::add_block_method`].
+    ///
+    /// # Safety
+    ///
+    /// The signature of `block` must be compatible with `sel`, and anything
+    /// the block captures must outlive the class, since the copy is never
+    /// released.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the block does not take a receiver, if the number of
+    /// arguments does not match the selector, or if the class already has
+    /// a class method with this selector.
+    pub unsafe fn add_block_class_method<A, R>(&mut self, sel: Sel, block: &Block<A, R>)
+    where
+        A: BlockArguments + EncodeArguments,
+        R: Encode,
+    {
+        add_block_method_to(self.cls.metaclass(), sel, block)
+    }
+
+    /// Resolve instance methods of the class dynamically.
+    ///
+    /// `resolver` is called from `+resolveInstanceMethod:` with the
+    /// receiving class and the selector, when an instance of the class or
+    /// one of its subclasses receives a message that it has no method for.
+    /// It may add a method, such as with [`Class::add_method`], and returns
+    /// whether it did. If it returns `false`, the superclass is asked to
+    /// resolve the method instead. The runtime calls it on whichever thread
+    /// first sends the message.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the class already has a `+resolveInstanceMethod:` method.
+    pub fn add_instance_method_resolver<F>(&mut self, resolver: F)
+    where
+        F: Fn(Class, Sel) -> bool + Send + Sync + 'static,
+    {
+        let block = resolver_block(self.cls, sel!(resolveInstanceMethod:), resolver);
+        unsafe { self.add_block_class_method(sel!(resolveInstanceMethod:), &block) };
+    }
+
+    /// Resolve class methods of the class dynamically.
+    ///
+    /// This works in the same way as
+    /// [`ClassDecl::add_instance_method_resolver`], but `resolver` is
+    /// called from `+resolveClassMethod:`. Class methods should be added to
+    /// the [metaclass](Class::metaclass) of the receiving class.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the class already has a `+resolveClassMethod:` method.
+    pub fn add_class_method_resolver<F>(&mut self, resolver: F)
+    where
+        F: Fn(Class, Sel) -> bool + Send + Sync + 'static,
+    {
+        let block = resolver_block(self.cls, sel!(resolveClassMethod:), resolver);
+        unsafe { self.add_block_class_method(sel!(resolveClassMethod:), &block) };
+    }
+
+    /// Add an instance variable of type `T` to the class.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the instance variable could not be added, e.g. because
+    /// the class already has one with the same name.
+    pub fn add_ivar<T: Encode>(&mut self, name: &CStr) {
+        let size = mem::size_of::<T>();
+        let alignment = mem::align_of::<T>().trailing_zeros() as u8;
+        let types = encode::type_encoding(&T::ENCODING);
+
+        let success = unsafe {
+            ffi::class_addIvar(
+                self.cls.as_ptr(),
+                name.as_ptr(),
+                size,
+                alignment,
+                types.as_ptr() as *const c_char,
+            )
+        };
+        assert!(
+            success.as_bool(),
+            "Failed to add instance variable {:?}",
+            name
+        );
+    }
+
+    /// Declare that the class conforms to the given protocol.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the protocol could not be added to the class.
+    pub fn add_protocol(&mut self, protocol: Protocol) {
+        let success = unsafe { ffi::class_addProtocol(self.cls.as_ptr(), protocol.as_ptr()) };
+        assert!(success.as_bool(), "Failed to add protocol {:?}", protocol);
+    }
+
+    /// Register the class with the runtime, making it usable.
+    pub fn register(self) -> Class {
+        let cls = self.cls;
+        mem::forget(self);
+
+        unsafe { ffi::objc_registerClassPair(cls.as_ptr()) };
+        cls
+    }
+}
+
+impl Drop for ClassDecl {
+    fn drop(&mut self) {
+        unsafe { ffi::objc_disposeClassPair(self.cls.as_ptr()) };
+    }
+}
+
+impl Class {
+    /// Add an instance method to this class after it has been registered.
+    ///
+    /// Returns `false` if the class already has a method with this
+    /// selector. To add a class method, call this on the
+    /// [metaclass](Class::metaclass).
+    ///
+    /// # Safety
+    ///
+    /// The signature of `imp` must be compatible with `sel`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of arguments does not match the selector.
+    pub unsafe fn add_method<F: MethodImplementation>(&self, sel: Sel, imp: F) -> bool {
+        add_method_to(*self, sel, imp)
+    }
+
+    /// Set the implementation of an instance method of this class to a
+    /// block, adding the method if the class does not already have it.
+    ///
+    /// If the previous implementation was created from a block, that block
+    /// is released. To set a class method, call this on the
+    /// [metaclass](Class::metaclass).
+    ///
+    /// # Safety
+    ///
+    /// The signature of `block` must be compatible with `sel`, and the
+    /// previous implementation must not be in use elsewhere. Anything the
+    /// block captures must outlive the method, since the block is copied
+    /// into it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the block does not take a receiver, or if the number of
+    /// arguments does not match the selector.
+    pub unsafe fn set_block_method<A, R>(&self, sel: Sel, block: &Block<A, R>)
+    where
+        A: BlockArguments + EncodeArguments,
+        R: Encode,
+    {
+        let types = block_method_encoding::<A, R>(sel);
+        let previous = ffi::class_replaceMethod(
+            self.as_ptr(),
+            sel.as_ptr(),
+            block.to_imp(),
+            types.as_ptr() as *const c_char,
+        );
+
+        if let Some(previous) = previous {
+            if !ffi::imp_getBlock(previous).is_null() {
+                ffi::imp_removeBlock(previous);
+            }
+        }
+    }
+}
+
+unsafe fn add_method_to<F: MethodImplementation>(cls: Class, sel: Sel, imp: F) -> bool {
+    check_argument_count(sel, F::ARGUMENTS.len() - 2);
+
+    let types = encode::method_type_encoding(&F::RETURN, F::ARGUMENTS);
+    ffi::class_addMethod(
+        cls.as_ptr(),
+        sel.as_ptr(),
+        imp.imp(),
+        types.as_ptr() as *const c_char,
+    )
+    .as_bool()
+}
+
+/// Build the block implementing a method resolver for `cls`, where
+/// `resolve_sel` is the selector of the resolver method.
+fn resolver_block<F>(cls: Class, resolve_sel: Sel, resolver: F) -> RcBlock<(Class, Sel), Bool>
+where
+    F: Fn(Class, Sel) -> bool + Send + Sync + 'static,
+{
+    RcBlock::new(move |receiver: Class, sel: Sel| {
+        if resolver(receiver, sel) {
+            return Bool::YES;
+        }
+
+        // fall back on the resolver of the superclass, if it has one
+        match cls.superclass() {
+            Some(superclass) if superclass.metaclass().responds_to(resolve_sel) => unsafe {
+                send_super_message(receiver, superclass, resolve_sel, (sel,), false)
+                    .unwrap_or(Bool::NO)
+            },
+            _ => Bool::NO,
+        }
+    })
+}
+
+unsafe fn add_block_method_to<A, R>(cls: Class, sel: Sel, block: &Block<A, R>)
+where
+    A: BlockArguments + EncodeArguments,
+    R: Encode,
+{
+    let types = block_method_encoding::<A, R>(sel);
+    let imp = block.to_imp();
+    let success = ffi::class_addMethod(
+        cls.as_ptr(),
+        sel.as_ptr(),
+        imp,
+        types.as_ptr() as *const c_char,
+    );
+
+    if !success.as_bool() {
+        ffi::imp_removeBlock(imp);
+        panic!("Failed to add method {:?}", sel);
+    }
+}
+
+/// Build the type encoding of a method implemented by a block, whose
+/// arguments are the receiver followed by the method's arguments.
+fn block_method_encoding<A: EncodeArguments, R: Encode>(sel: Sel) -> String {
+    let (receiver, args) = A::ENCODINGS
+        .split_first()
+        .expect("A block implementing a method must take the receiver");
+    assert!(
+        matches!(receiver, Encoding::Object | Encoding::Class),
+        "A block implementing a method must take an object or class as its receiver, not `{}`",
+        receiver
+    );
+    check_argument_count(sel, args.len());
+
+    let mut encodings = Vec::with_capacity(args.len() + 2);
+    encodings.extend_from_slice(&[*receiver, Encoding::Sel]);
+    encodings.extend_from_slice(args);
+    encode::method_type_encoding(&R::ENCODING, &encodings)
+}
+
+/// Check that a selector takes the given number of arguments.
+///
+/// The receiver and the selector are not part of the selector's name, so
+/// they are not counted.
+fn check_argument_count(sel: Sel, actual: usize) {
+    let expected = sel.name().matches(':').count();
+    assert!(
+        expected == actual,
+        "Selector {:?} takes {} arguments, but {} were given",
+        sel,
+        expected,
+        actual
+    );
+}
+
+/// A protocol that is being declared at runtime.
+///
+/// A protocol that is dropped without being registered is leaked, since
+/// the runtime has no way of disposing of it.
+#[derive(Debug)]
+pub struct ProtocolDecl {
+    protocol: Protocol,
+}
+
+impl ProtocolDecl {
+    /// Start declaring a new protocol with the given name.
+    ///
+    /// Returns `None` if a protocol with the same name already exists.
+    pub fn new(name: &CStr) -> Option<ProtocolDecl> {
+        let ptr = unsafe { ffi::objc_allocateProtocol(name.as_ptr()) };
+        if ptr.is_null() {
+            return None;
+        }
+
+        Some(ProtocolDecl {
+            protocol: unsafe { Protocol::from_ptr(ptr) },
+        })
+    }
+
+    /// Add the description of an instance method to the protocol.
+    ///
+    /// The type encoding of the method is derived from `Args` and `Ret`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of arguments does not match the selector.
+    pub fn add_method_description<Args: EncodeArguments, Ret: Encode>(
+        &mut self,
+        sel: Sel,
+        required: bool,
+    ) {
+        self.add_description::<Args, Ret>(sel, required, true)
+    }
+
+    /// Add the description of a class method to the protocol.
+    ///
+    /// The type encoding of the method is derived from `Args` and `Ret`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of arguments does not match the selector.
+    pub fn add_class_method_description<Args: EncodeArguments, Ret: Encode>(
+        &mut self,
+        sel: Sel,
+        required: bool,
+    ) {
+        self.add_description::<Args, Ret>(sel, required, false)
+    }
+
+    fn add_description<Args: EncodeArguments, Ret: Encode>(
+        &mut self,
+        sel: Sel,
+        required: bool,
+        instance: bool,
+    ) {
+        check_argument_count(sel, Args::ENCODINGS.len());
+
+        let mut args = Vec::with_capacity(Args::ENCODINGS.len() + 2);
+        args.extend_from_slice(&[Encoding::Object, Encoding::Sel]);
+        args.extend_from_slice(Args::ENCODINGS);
+        let types = encode::method_type_encoding(&Ret::ENCODING, &args);
+
+        unsafe {
+            ffi::protocol_addMethodDescription(
+                self.protocol.as_ptr(),
+                sel.as_ptr(),
+                types.as_ptr() as *const c_char,
+                required.into(),
+                instance.into(),
+            )
+        };
+    }
+
+    /// Declare that the protocol conforms to another protocol.
+    pub fn add_protocol(&mut self, protocol: Protocol) {
+        unsafe { ffi::protocol_addProtocol(self.protocol.as_ptr(), protocol.as_ptr()) };
+    }
+
+    /// Add an instance property to the protocol.
+    pub fn add_property(
+        &mut self,
+        name: &CStr,
+        attributes: PropertyAttributes<'_>,
+        required: bool,
+    ) {
+        // the runtime expects a list of nul-terminated name and value pairs
+        let mut pairs: Vec<(&str, String)> = Vec::new();
+        let mut push = |name: &'static str, value: &str| {
+            let mut value = String::from(value);
+            value.push('\0');
+            pairs.push((name, value));
+        };
+
+        push("T\0", attributes.type_encoding());
+        if attributes.is_readonly() {
+            push("R\0", "");
+        }
+        match attributes.setter_semantics() {
+            SetterSemantics::Assign => {}
+            SetterSemantics::Retain => push("&\0", ""),
+            SetterSemantics::Copy => push("C\0", ""),
+            SetterSemantics::Weak => push("W\0", ""),
+        }
+        if attributes.is_nonatomic() {
+            push("N\0", "");
+        }
+        if attributes.is_dynamic() {
+            push("D\0", "");
+        }
+        if let Some(getter) = attributes.getter() {
+            push("G\0", getter);
+        }
+        if let Some(setter) = attributes.setter() {
+            push("S\0", setter);
+        }
+        if let Some(ivar) = attributes.ivar() {
+            push("V\0", ivar);
+        }
+
+        let raw: Vec<ffi::PropertyAttribute> = pairs
+            .iter()
+            .map(|(name, value)| ffi::PropertyAttribute {
+                name: name.as_ptr() as *const c_char,
+                value: value.as_ptr() as *const c_char,
+            })
+            .collect();
+
+        unsafe {
+            ffi::protocol_addProperty(
+                self.protocol.as_ptr(),
+                name.as_ptr(),
+                raw.as_ptr(),
+                raw.len() as libc::c_uint,
+                required.into(),
+                true.into(),
+            )
+        };
+    }
+
+    /// Register the protocol with the runtime, making it usable.
+    pub fn register(self) -> Protocol {
+        unsafe { ffi::objc_registerProtocol(self.protocol.as_ptr()) };
+        self.protocol
+    }
+}
@@ -0,0 +1,782 @@
+// BSL 1.0 License
+
+//! Type encodings for Objective C types.
+//!
+//! The runtime describes the types of methods, instance variables and
+//! properties using strings like `v@:i`. The [`Encode`] trait provides
+//! the equivalent of the `@encode` directive for Rust types.
+
+use crate::{cstr::c_char, Autoreleased, Block, Class, Id, Object, ObjectType, Protocol, Sel};
+use alloc::string::String;
+use core::{ffi::c_void, fmt, fmt::Write, ptr::NonNull};
+
+/// An Objective C type encoding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Encoding<'a> {
+    /// A C `char`.
+    Char,
+    /// A C `short`.
+    Short,
+    /// A C `int`.
+    Int,
+    /// A C `long`.
+    Long,
+    /// A C `long long`.
+    LongLong,
+    /// A C `unsigned char`.
+    UChar,
+    /// A C `unsigned short`.
+    UShort,
+    /// A C `unsigned int`.
+    UInt,
+    /// A C `unsigned long`.
+    ULong,
+    /// A C `unsigned long long`.
+    ULongLong,
+    /// A C `float`.
+    Float,
+    /// A C `double`.
+    Double,
+    /// A C `_Bool`.
+    Bool,
+    /// A C `void`.
+    Void,
+    /// A C string, i.e. `char *`.
+    String,
+    /// An Objective C object, i.e. `id`.
+    Object,
+    /// An Objective C block.
+    Block,
+    /// An Objective C class, i.e. `Class`.
+    Class,
+    /// An Objective C selector, i.e. `SEL`.
+    Sel,
+    /// An unknown type, such as a function pointer.
+    Unknown,
+    /// A bitfield with the given number of bits.
+    BitField(u8),
+    /// A pointer to the given type.
+    Pointer(&'a Encoding<'a>),
+    /// An array with the given length and element type.
+    Array(usize, &'a Encoding<'a>),
+    /// A structure with the given name and fields.
+    Struct(&'a str, &'a [Encoding<'a>]),
+    /// A union with the given name and fields.
+    Union(&'a str, &'a [Encoding<'a>]),
+}
+
+impl Encoding<'_> {
+    /// Tell whether this encoding is equivalent to the given encoding
+    /// string, as returned by the runtime.
+    ///
+    /// Type qualifiers such as `r` for `const`, and offsets following the
+    /// type, are ignored.
+    pub fn equivalent_to_str(&self, s: &str) -> bool {
+        matches!(self.strip_prefix(s), Some(rest) if skip_offset(rest).is_empty())
+    }
+
+    /// Remove this encoding from the start of the given encoding string,
+    /// returning the rest of the string.
+    pub(crate) fn strip_prefix<'s>(&self, s: &'s str) -> Option<&'s str> {
+        let s = strip_qualifiers(s);
+
+        match self {
+            Encoding::Object => {
+                // object encodings may be followed by a quoted class name
+                let rest = s.strip_prefix('@')?;
+                if rest.starts_with('?') {
+                    return None;
+                }
+
+                match rest.strip_prefix('"') {
+                    Some(quoted) => quoted.find('"').map(|end| &quoted[end + 1..]),
+                    None => Some(rest),
+                }
+            }
+            Encoding::String => s
+                .strip_prefix('*')
+                .or_else(|| Encoding::Pointer(&Encoding::Char).strip_prefix(s)),
+            Encoding::Long | Encoding::LongLong if core::mem::size_of::<libc::c_long>() == 8 => {
+                // on LP64 platforms, `long` may be encoded as `long long`
+                s.strip_prefix('l').or_else(|| s.strip_prefix('q'))
+            }
+            Encoding::ULong | Encoding::ULongLong if core::mem::size_of::<libc::c_ulong>() == 8 => {
+                s.strip_prefix('L').or_else(|| s.strip_prefix('Q'))
+            }
+            Encoding::BitField(bits) => {
+                let rest = s.strip_prefix('b')?;
+                let (len, rest) = split_number(rest);
+                (len == Some(*bits as usize)).then_some(rest)
+            }
+            Encoding::Pointer(Encoding::Char) => s
+                .strip_prefix('*')
+                .or_else(|| Encoding::Char.strip_prefix(s.strip_prefix('^')?)),
+            Encoding::Pointer(target) => target.strip_prefix(s.strip_prefix('^')?),
+            Encoding::Array(len, item) => {
+                let (found, rest) = split_number(s.strip_prefix('[')?);
+                if found != Some(*len) {
+                    return None;
+                }
+
+                item.strip_prefix(rest)?.strip_prefix(']')
+            }
+            Encoding::Struct(name, fields) => strip_fields(s, ('{', '}'), name, fields),
+            Encoding::Union(name, fields) => strip_fields(s, ('(', ')'), name, fields),
+            simple => s.strip_prefix(simple.code()?),
+        }
+    }
+
+    /// Get the code for encodings that consist of a fixed string.
+    const fn code(&self) -> Option<&'static str> {
+        Some(match self {
+            Encoding::Char => "c",
+            Encoding::Short => "s",
+            Encoding::Int => "i",
+            Encoding::Long => "l",
+            Encoding::LongLong => "q",
+            Encoding::UChar => "C",
+            Encoding::UShort => "S",
+            Encoding::UInt => "I",
+            Encoding::ULong => "L",
+            Encoding::ULongLong => "Q",
+            Encoding::Float => "f",
+            Encoding::Double => "d",
+            Encoding::Bool => "B",
+            Encoding::Void => "v",
+            Encoding::String => "*",
+            Encoding::Object => "@",
+            Encoding::Block => "@?",
+            Encoding::Class => "#",
+            Encoding::Sel => ":",
+            Encoding::Unknown => "?",
+            _ => return None,
+        })
+    }
+}
+
+/// Type qualifiers that may precede a type in an encoding string.
+const QUALIFIERS: &[char] = &['r', 'n', 'N', 'o', 'O', 'R', 'V', 'A', 'j'];
+
+/// Remove the type qualifiers from the start of an encoding string.
+pub(crate) fn strip_qualifiers(s: &str) -> &str {
+    s.trim_start_matches(QUALIFIERS)
+}
+
+/// Skip the offset that the runtime places after types in method
+/// encodings.
+pub(crate) fn skip_offset(s: &str) -> &str {
+    let s = s.strip_prefix('-').unwrap_or(s);
+    s.trim_start_matches(|c: char| c.is_ascii_digit())
+}
+
+fn split_number(s: &str) -> (Option<usize>, &str) {
+    let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    (s[..end].parse().ok(), &s[end..])
+}
+
+fn strip_fields<'s>(
+    s: &'s str,
+    (open, close): (char, char),
+    name: &str,
+    fields: &[Encoding<'_>],
+) -> Option<&'s str> {
+    let rest = s.strip_prefix(open)?.strip_prefix(name)?;
+
+    // structures behind pointers may be encoded without their fields
+    if let Some(rest) = rest.strip_prefix(close) {
+        return Some(rest);
+    }
+
+    let mut rest = rest.strip_prefix('=')?;
+    for field in fields {
+        // fields may be preceded by their quoted names
+        if let Some(quoted) = rest.strip_prefix('"') {
+            rest = &quoted[quoted.find('"')? + 1..];
+        }
+
+        rest = field.strip_prefix(rest)?;
+    }
+
+    rest.strip_prefix(close)
+}
+
+impl fmt::Display for Encoding<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Encoding::BitField(bits) => write!(f, "b{}", bits),
+            Encoding::Pointer(target) => write!(f, "^{}", target),
+            Encoding::Array(len, item) => write!(f, "[{}{}]", len, item),
+            Encoding::Struct(name, fields) => write_fields(f, ('{', '}'), name, fields),
+            Encoding::Union(name, fields) => write_fields(f, ('(', ')'), name, fields),
+            simple => f.write_str(simple.code().expect("All other encodings are simple")),
+        }
+    }
+}
+
+fn write_fields(
+    f: &mut fmt::Formatter<'_>,
+    (open, close): (char, char),
+    name: &str,
+    fields: &[Encoding<'_>],
+) -> fmt::Result {
+    write!(f, "{}{}=", open, name)?;
+    for field in fields {
+        fmt::Display::fmt(field, f)?;
+    }
+    f.write_char(close)
+}
+
+/// A type that has an Objective C type encoding.
+///
+/// This can be implemented for `#[repr(C)]` structures by describing
+/// their fields with [`Encoding::Struct`].
+///
+/// # Safety
+///
+/// The encoding must accurately describe the type's layout and ABI.
+/// `OWNS_OBJECT` may only be `true` for pointers to objects that release
+/// the object when they are dropped.
+pub unsafe trait Encode {
+    /// The Objective C type encoding for this type.
+    const ENCODING: Encoding<'static>;
+
+    /// Whether this type owns a retain count on the object that it points
+    /// to, such as [`Id`].
+    ///
+    /// Objects returned by messages as such a type are retained, unless the
+    /// method already returns them retained.
+    const OWNS_OBJECT: bool = false;
+}
+
+macro_rules! encode_impls {
+    ($($ty: ty => $encoding: expr,)*) => {
+        $(
+            unsafe impl Encode for $ty {
+                const ENCODING: Encoding<'static> = $encoding;
+            }
+        )*
+    };
+}
+
+encode_impls! {
+    i8 => Encoding::Char,
+    i16 => Encoding::Short,
+    i32 => Encoding::Int,
+    i64 => Encoding::LongLong,
+    u8 => Encoding::UChar,
+    u16 => Encoding::UShort,
+    u32 => Encoding::UInt,
+    u64 => Encoding::ULongLong,
+    f32 => Encoding::Float,
+    f64 => Encoding::Double,
+    bool => Encoding::Bool,
+    () => Encoding::Void,
+    c_void => Encoding::Void,
+    Object => Encoding::Object,
+    Option<Object> => Encoding::Object,
+    Class => Encoding::Class,
+    Option<Class> => Encoding::Class,
+    Sel => Encoding::Sel,
+    Option<Sel> => Encoding::Sel,
+    Protocol => Encoding::Object,
+    Option<Protocol> => Encoding::Object,
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(target_pointer_width = "64")] {
+        encode_impls! {
+            isize => Encoding::LongLong,
+            usize => Encoding::ULongLong,
+        }
+    } else {
+        encode_impls! {
+            isize => Encoding::Long,
+            usize => Encoding::ULong,
+        }
+    }
+}
+
+unsafe impl<T: Encode> Encode for *const T {
+    const ENCODING: Encoding<'static> = Encoding::Pointer(&T::ENCODING);
+}
+
+unsafe impl<T: Encode> Encode for *mut T {
+    const ENCODING: Encoding<'static> = Encoding::Pointer(&T::ENCODING);
+}
+
+unsafe impl<T: Encode> Encode for NonNull<T> {
+    const ENCODING: Encoding<'static> = Encoding::Pointer(&T::ENCODING);
+}
+
+unsafe impl<T: Encode> Encode for Option<NonNull<T>> {
+    const ENCODING: Encoding<'static> = Encoding::Pointer(&T::ENCODING);
+}
+
+unsafe impl<T: Encode, const N: usize> Encode for [T; N] {
+    const ENCODING: Encoding<'static> = Encoding::Array(N, &T::ENCODING);
+}
+
+unsafe impl<T: ObjectType> Encode for Id<T> {
+    const ENCODING: Encoding<'static> = Encoding::Object;
+    const OWNS_OBJECT: bool = true;
+}
+
+unsafe impl<T: ObjectType> Encode for Option<Id<T>> {
+    const ENCODING: Encoding<'static> = Encoding::Object;
+    const OWNS_OBJECT: bool = true;
+}
+
+unsafe impl<T: ObjectType> Encode for Autoreleased<'_, T> {
+    const ENCODING: Encoding<'static> = Encoding::Object;
+}
+
+unsafe impl<T: ObjectType> Encode for Option<Autoreleased<'_, T>> {
+    const ENCODING: Encoding<'static> = Encoding::Object;
+}
+
+unsafe impl<A, R> Encode for &Block<A, R> {
+    const ENCODING: Encoding<'static> = Encoding::Block;
+}
+
+unsafe impl<A, R> Encode for Option<&Block<A, R>> {
+    const ENCODING: Encoding<'static> = Encoding::Block;
+}
+
+/// A type that can be passed as the argument of a message.
+///
+/// This is implemented for all types that implement [`Encode`], which are
+/// passed as they are. References to an [`Id`] are passed as the object
+/// that they point to.
+///
+/// # Safety
+///
+/// `ENCODING` must accurately describe the layout and ABI of `Raw`.
+pub unsafe trait EncodeArgument {
+    /// The Objective C type encoding of the argument.
+    const ENCODING: Encoding<'static>;
+
+    /// The type that is actually passed to Objective C.
+    type Raw;
+
+    /// Convert this argument into the value passed to Objective C.
+    fn into_raw(self) -> Self::Raw;
+}
+
+unsafe impl<T: Encode> EncodeArgument for T {
+    const ENCODING: Encoding<'static> = T::ENCODING;
+    type Raw = T;
+
+    fn into_raw(self) -> T {
+        self
+    }
+}
+
+unsafe impl<T: ObjectType> EncodeArgument for &Id<T> {
+    const ENCODING: Encoding<'static> = Encoding::Object;
+    type Raw = Object;
+
+    fn into_raw(self) -> Object {
+        self.as_object()
+    }
+}
+
+unsafe impl<T: ObjectType> EncodeArgument for Option<&Id<T>> {
+    const ENCODING: Encoding<'static> = Encoding::Object;
+    type Raw = Option<Object>;
+
+    fn into_raw(self) -> Option<Object> {
+        self.map(|obj| obj.as_object())
+    }
+}
+
+/// A list of types that have Objective C type encodings.
+///
+/// This is implemented for tuples of types that implement [`Encode`].
+pub trait EncodeArguments {
+    /// The type encodings of the types in the list.
+    const ENCODINGS: &'static [Encoding<'static>];
+}
+
+macro_rules! encode_function {
+    ($($ident: ident)*) => {
+        unsafe impl<Return, $($ident),*> Encode for extern "C" fn($($ident),*) -> Return {
+            const ENCODING: Encoding<'static> = Encoding::Pointer(&Encoding::Unknown);
+        }
+
+        unsafe impl<Return, $($ident),*> Encode for unsafe extern "C" fn($($ident),*) -> Return {
+            const ENCODING: Encoding<'static> = Encoding::Pointer(&Encoding::Unknown);
+        }
+
+        unsafe impl<Return, $($ident),*> Encode for Option<extern "C" fn($($ident),*) -> Return> {
+            const ENCODING: Encoding<'static> = Encoding::Pointer(&Encoding::Unknown);
+        }
+
+        unsafe impl<Return, $($ident),*> Encode
+            for Option<unsafe extern "C" fn($($ident),*) -> Return>
+        {
+            const ENCODING: Encoding<'static> = Encoding::Pointer(&Encoding::Unknown);
+        }
+
+        impl<$($ident: Encode),*> EncodeArguments for ($($ident,)*) {
+            const ENCODINGS: &'static [Encoding<'static>] = &[$($ident::ENCODING),*];
+        }
+    };
+}
+
+macro_rules! encode_functions {
+    () => {
+        encode_function! {}
+    };
+    ($head: ident $($ident: ident)*) => {
+        encode_function! { $head $($ident)* }
+        encode_functions! { $($ident)* }
+    };
+}
+
+encode_functions! {
+    A B C D E F G H I J K L M N O P Q R S T U V W X Y Z
+}
+
+/// Tell whether a method type encoding, as returned by the runtime,
+/// matches the given return and argument types.
+///
+/// `args` does not include the receiver and the selector. The receiver
+/// may be either an object or a class.
+pub(crate) fn signature_matches(types: &str, ret: &Encoding<'_>, args: &[Encoding<'_>]) -> bool {
+    fn strip_signature<'s>(
+        types: &'s str,
+        ret: &Encoding<'_>,
+        args: &[Encoding<'_>],
+    ) -> Option<&'s str> {
+        let mut rest = skip_offset(ret.strip_prefix(types)?);
+
+        let receiver = Encoding::Object
+            .strip_prefix(rest)
+            .or_else(|| Encoding::Class.strip_prefix(rest))?;
+        rest = skip_offset(receiver);
+        rest = skip_offset(Encoding::Sel.strip_prefix(rest)?);
+
+        for arg in args {
+            rest = skip_offset(arg.strip_prefix(rest)?);
+        }
+
+        Some(rest)
+    }
+
+    strip_signature(types, ret, args) == Some("")
+}
+
+/// Build the nul-terminated type encoding for a method.
+pub(crate) fn method_type_encoding(ret: &Encoding<'_>, args: &[Encoding<'_>]) -> String {
+    let mut types = String::new();
+    write!(types, "{}", ret).expect("Writing to a String should never fail");
+    for arg in args {
+        write!(types, "{}", arg).expect("Writing to a String should never fail");
+    }
+
+    types.push('\0');
+    types
+}
+
+/// Build the nul-terminated type encoding for a single type.
+pub(crate) fn type_encoding(encoding: &Encoding<'_>) -> String {
+    method_type_encoding(encoding, &[])
+}
+
+/// The maximum length of a type encoding built at compile time, including
+/// the nul terminator.
+const MAX_STATIC_ENCODING: usize = 256;
+
+/// A nul-terminated type encoding built at compile time.
+///
+/// Encodings that are too long to fit are left empty.
+pub(crate) struct StaticEncoding {
+    bytes: [u8; MAX_STATIC_ENCODING],
+    len: usize,
+}
+
+impl StaticEncoding {
+    /// Build the type encoding for a block, which takes the block itself
+    /// as its first argument.
+    pub(crate) const fn block(ret: &Encoding<'_>, args: &[Encoding<'_>]) -> StaticEncoding {
+        let mut encoding = StaticEncoding {
+            bytes: [0; MAX_STATIC_ENCODING],
+            len: 0,
+        };
+
+        encoding.push(ret);
+        encoding.push(&Encoding::Block);
+        let mut i = 0;
+        while i < args.len() {
+            encoding.push(&args[i]);
+            i += 1;
+        }
+
+        if encoding.len >= MAX_STATIC_ENCODING {
+            encoding.bytes = [0; MAX_STATIC_ENCODING];
+            encoding.len = 0;
+        }
+        encoding
+    }
+
+    /// Get a pointer to the encoding, or null if it was too long.
+    pub(crate) const fn as_ptr(&self) -> *const c_char {
+        if self.len == 0 {
+            core::ptr::null()
+        } else {
+            self.bytes.as_ptr() as *const c_char
+        }
+    }
+
+    const fn push(&mut self, encoding: &Encoding<'_>) {
+        match *encoding {
+            Encoding::BitField(bits) => {
+                self.push_str("b");
+                self.push_number(bits as usize);
+            }
+            Encoding::Pointer(target) => {
+                self.push_str("^");
+                self.push(target);
+            }
+            Encoding::Array(len, item) => {
+                self.push_str("[");
+                self.push_number(len);
+                self.push(item);
+                self.push_str("]");
+            }
+            Encoding::Struct(name, fields) => self.push_fields(("{", "}"), name, fields),
+            Encoding::Union(name, fields) => self.push_fields(("(", ")"), name, fields),
+            ref simple => match simple.code() {
+                Some(code) => self.push_str(code),
+                None => panic!("All other encodings are simple"),
+            },
+        }
+    }
+
+    const fn push_fields(
+        &mut self,
+        (open, close): (&str, &str),
+        name: &str,
+        fields: &[Encoding<'_>],
+    ) {
+        self.push_str(open);
+        self.push_str(name);
+        self.push_str("=");
+        let mut i = 0;
+        while i < fields.len() {
+            self.push(&fields[i]);
+            i += 1;
+        }
+        self.push_str(close);
+    }
+
+    const fn push_number(&mut self, mut number: usize) {
+        let mut digits = [0; 20];
+        let mut count = 0;
+        loop {
+            digits[count] = b'0' + (number % 10) as u8;
+            count += 1;
+            number /= 10;
+            if number == 0 {
+                break;
+            }
+        }
+
+        while count > 0 {
+            count -= 1;
+            self.push_byte(digits[count]);
+        }
+    }
+
+    const fn push_str(&mut self, s: &str) {
+        let bytes = s.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            self.push_byte(bytes[i]);
+            i += 1;
+        }
+    }
+
+    const fn push_byte(&mut self, byte: u8) {
+        // the last byte is kept for the nul terminator
+        if self.len < MAX_STATIC_ENCODING - 1 {
+            self.bytes[self.len] = byte;
+            self.len += 1;
+        } else {
+            self.len = MAX_STATIC_ENCODING;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cstr::CStr;
+    use alloc::string::ToString;
+
+    const POINT: Encoding<'static> =
+        Encoding::Struct("CGPoint", &[Encoding::Double, Encoding::Double]);
+
+    #[test]
+    fn skip_offset_removes_leading_digits() {
+        assert_eq!(skip_offset("16@0:8"), "@0:8");
+        assert_eq!(skip_offset("-8i"), "i");
+        assert_eq!(skip_offset("@0:8"), "@0:8");
+        assert_eq!(skip_offset(""), "");
+    }
+
+    #[test]
+    fn simple_encodings() {
+        assert!(Encoding::Int.equivalent_to_str("i"));
+        assert!(Encoding::Int.equivalent_to_str("i4"));
+        assert!(Encoding::Void.equivalent_to_str("v"));
+        assert!(Encoding::Sel.equivalent_to_str(":"));
+        assert!(Encoding::Class.equivalent_to_str("#"));
+        assert!(Encoding::Bool.equivalent_to_str("B"));
+        assert!(!Encoding::Int.equivalent_to_str("I"));
+        assert!(!Encoding::Int.equivalent_to_str("ii"));
+        assert!(!Encoding::Int.equivalent_to_str(""));
+    }
+
+    #[test]
+    fn qualifiers_are_ignored() {
+        assert!(Encoding::Int.equivalent_to_str("ri"));
+        assert!(Encoding::Object.equivalent_to_str("Vo@"));
+        assert_eq!(Encoding::Int.strip_prefix("ni8"), Some("8"));
+    }
+
+    #[test]
+    fn objects_may_have_class_names() {
+        assert!(Encoding::Object.equivalent_to_str("@"));
+        assert!(Encoding::Object.equivalent_to_str("@\"NSString\""));
+        assert!(Encoding::Object.equivalent_to_str("@\"NSString\"16"));
+        assert_eq!(Encoding::Object.strip_prefix("@\"NSString\"i"), Some("i"));
+        assert!(!Encoding::Object.equivalent_to_str("@\"NSString"));
+        assert!(!Encoding::Object.equivalent_to_str("#"));
+    }
+
+    #[test]
+    fn blocks_are_not_objects() {
+        assert!(Encoding::Block.equivalent_to_str("@?"));
+        assert!(!Encoding::Object.equivalent_to_str("@?"));
+        assert!(!Encoding::Block.equivalent_to_str("@"));
+    }
+
+    #[test]
+    fn strings_and_char_pointers_are_equivalent() {
+        assert!(Encoding::String.equivalent_to_str("*"));
+        assert!(Encoding::String.equivalent_to_str("r*"));
+        assert!(Encoding::String.equivalent_to_str("^c"));
+        assert!(Encoding::Pointer(&Encoding::Char).equivalent_to_str("*"));
+        assert!(Encoding::Pointer(&Encoding::Char).equivalent_to_str("^c"));
+        assert!(!Encoding::String.equivalent_to_str("^C"));
+    }
+
+    #[test]
+    fn long_is_equivalent_to_long_long_on_lp64() {
+        let lp64 = core::mem::size_of::<libc::c_long>() == 8;
+        assert!(Encoding::Long.equivalent_to_str("l"));
+        assert!(Encoding::LongLong.equivalent_to_str("q"));
+        assert_eq!(Encoding::Long.equivalent_to_str("q"), lp64);
+        assert_eq!(Encoding::LongLong.equivalent_to_str("l"), lp64);
+        assert_eq!(Encoding::ULong.equivalent_to_str("Q"), lp64);
+        assert_eq!(Encoding::ULongLong.equivalent_to_str("L"), lp64);
+        assert!(!Encoding::Long.equivalent_to_str("L"));
+        assert!(!Encoding::ULongLong.equivalent_to_str("q"));
+    }
+
+    #[test]
+    fn bitfields_must_have_the_same_width() {
+        assert!(Encoding::BitField(3).equivalent_to_str("b3"));
+        assert!(!Encoding::BitField(3).equivalent_to_str("b4"));
+        assert!(!Encoding::BitField(3).equivalent_to_str("b"));
+    }
+
+    #[test]
+    fn pointers_and_arrays() {
+        assert!(Encoding::Pointer(&Encoding::Int).equivalent_to_str("^i"));
+        assert!(Encoding::Pointer(&Encoding::Pointer(&Encoding::Object)).equivalent_to_str("^^@"));
+        assert!(!Encoding::Pointer(&Encoding::Int).equivalent_to_str("i"));
+        assert!(Encoding::Array(4, &Encoding::Int).equivalent_to_str("[4i]"));
+        assert!(!Encoding::Array(4, &Encoding::Int).equivalent_to_str("[3i]"));
+        assert!(!Encoding::Array(4, &Encoding::Int).equivalent_to_str("[4i"));
+    }
+
+    #[test]
+    fn structs_and_unions() {
+        assert!(POINT.equivalent_to_str("{CGPoint=dd}"));
+        assert!(POINT.equivalent_to_str("{CGPoint=\"x\"d\"y\"d}"));
+        assert!(!POINT.equivalent_to_str("{CGPoint=df}"));
+        assert!(!POINT.equivalent_to_str("{CGSize=dd}"));
+        assert!(!POINT.equivalent_to_str("{CGPoint=d}"));
+
+        let union = Encoding::Union("Value", &[Encoding::Int, Encoding::Float]);
+        assert!(union.equivalent_to_str("(Value=if)"));
+        assert!(!union.equivalent_to_str("{Value=if}"));
+    }
+
+    #[test]
+    fn structs_behind_pointers_may_omit_fields() {
+        assert!(POINT.equivalent_to_str("{CGPoint}"));
+        assert!(Encoding::Pointer(&POINT).equivalent_to_str("^{CGPoint}"));
+        assert!(Encoding::Pointer(&POINT).equivalent_to_str("^{CGPoint=dd}"));
+    }
+
+    #[test]
+    fn display_matches_runtime_encodings() {
+        assert_eq!(POINT.to_string(), "{CGPoint=dd}");
+        assert_eq!(Encoding::Array(2, &Encoding::Block).to_string(), "[2@?]");
+        assert_eq!(Encoding::Pointer(&Encoding::BitField(5)).to_string(), "^b5");
+        assert_eq!(
+            method_type_encoding(
+                &Encoding::Void,
+                &[Encoding::Object, Encoding::Sel, Encoding::Int]
+            ),
+            "v@:i\0"
+        );
+    }
+
+    #[test]
+    fn static_block_encodings() {
+        let encoding = StaticEncoding::block(&POINT, &[Encoding::Object, Encoding::BitField(12)]);
+        let encoding = unsafe { CStr::from_ptr(encoding.as_ptr()) };
+        assert_eq!(encoding.to_str(), Ok("{CGPoint=dd}@?@b12"));
+
+        let encoding = StaticEncoding::block(&Encoding::Void, &[Encoding::Int; 255]);
+        assert!(encoding.as_ptr().is_null());
+    }
+
+    #[test]
+    fn signatures_with_offsets() {
+        assert!(signature_matches("r*16@0:8", &Encoding::String, &[]));
+        assert!(signature_matches(
+            "v20@0:8i16",
+            &Encoding::Void,
+            &[Encoding::Int]
+        ));
+        assert!(signature_matches(
+            "@\"NSString\"16@0:8",
+            &Encoding::Object,
+            &[]
+        ));
+        assert!(signature_matches("v16#0:8", &Encoding::Void, &[]));
+        assert!(signature_matches("v@:i", &Encoding::Void, &[Encoding::Int]));
+    }
+
+    #[test]
+    fn signatures_that_do_not_match() {
+        assert!(!signature_matches(
+            "v20@0:8i16",
+            &Encoding::Void,
+            &[Encoding::UInt]
+        ));
+        assert!(!signature_matches("v20@0:8i16", &Encoding::Void, &[]));
+        assert!(!signature_matches(
+            "v16@0:8",
+            &Encoding::Void,
+            &[Encoding::Int]
+        ));
+        assert!(!signature_matches("i16@0:8", &Encoding::Void, &[]));
+        assert!(!signature_matches("v16:0@8", &Encoding::Void, &[]));
+    }
+}
@@ -1,23 +1,52 @@
 // BSL 1.0 License
 
-use crate::{cstr::{c_char, CStr}, Object};
+use crate::{
+    autorelease::{autoreleasepool, Autoreleased},
+    cstr::{c_char, CStr},
+    Bool, Id, Object, Sel,
+};
+use alloc::string::String;
 use core::{
     fmt,
     hint::unreachable_unchecked,
     mem::{ManuallyDrop, MaybeUninit},
+    ptr,
 };
 
 /// Represents an error that can be emitted by the Objective C code.
-#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
-#[repr(C)]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Error {
-    id: Object,
+    repr: Repr,
+}
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Repr {
+    /// An Objective C exception was raised.
+    Exception(Id),
+    /// The types used to send a message did not match the method.
+    MismatchedTypes {
+        sel: Sel,
+        expected: String,
+        found: String,
+    },
+    /// A message that cannot return nil returned nil.
+    #[cfg_attr(
+        not(any(debug_assertions, feature = "verify_message")),
+        allow(dead_code)
+    )]
+    NilReturn(Sel),
 }
 
 impl Error {
     /// Get the underlying pointer backing this `Error`.
+    ///
+    /// Returns a null pointer if this error was not caused by an
+    /// Objective C exception.
     pub fn as_ptr(&self) -> *const () {
-        self.id.as_ptr()
+        match self.repr {
+            Repr::Exception(ref id) => id.as_ptr(),
+            _ => ptr::null(),
+        }
     }
 
     /// Create a new `Error` from an underlying pointer.
@@ -28,14 +57,46 @@ impl Error {
     /// object.
     pub unsafe fn from_ptr(ptr: *const ()) -> Error {
         Error {
-            id: Object::from_ptr(ptr),
+            repr: Repr::Exception(Id::from_retained(Object::from_ptr(ptr))),
+        }
+    }
+
+    /// Get the exception that caused this error, if any.
+    pub fn exception(&self) -> Option<&Id> {
+        match self.repr {
+            Repr::Exception(ref id) => Some(id),
+            _ => None,
+        }
+    }
+
+    /// Create an error for a message that returned nil, when its return
+    /// type cannot be nil.
+    #[cfg_attr(
+        not(any(debug_assertions, feature = "verify_message")),
+        allow(dead_code)
+    )]
+    pub(crate) fn nil_return(sel: Sel) -> Error {
+        Error {
+            repr: Repr::NilReturn(sel),
+        }
+    }
+
+    /// Create an error for a message that was sent with the wrong types.
+    pub(crate) fn mismatched_types(sel: Sel, expected: String, found: String) -> Error {
+        Error {
+            repr: Repr::MismatchedTypes {
+                sel,
+                expected,
+                found,
+            },
         }
     }
 }
 
-fn write_nsstring(nsstr: Object, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+fn write_nsstring(nsstr: Autoreleased<'_>, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     // get the UTF-8 encoded string
-    if let Ok(encoded_str) = unsafe { msg_send![*const c_char => nsstr, UTF8String] } {
+    let encoded_str = unsafe { msg_send![*const c_char => nsstr, UTF8String] };
+    if let Some(encoded_str) = encoded_str.ok().filter(|s| !s.is_null()) {
         // convert to a rust string
         let encoded_str = unsafe { CStr::from_ptr(encoded_str) };
 
@@ -48,43 +109,79 @@ fn write_nsstring(nsstr: Object, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     }
 }
 
-impl Clone for Error {
-    fn clone(&self) -> Self {
-        Error {
-            id: unsafe { msg_send![self.id, retain] }.expect("failed to retain error"),
-        }
-    }
-}
-
 impl fmt::Debug for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        struct PrintClassName<'a>(&'a Error);
+        struct PrintClassName<'a>(&'a Id);
 
         impl<'a> fmt::Debug for PrintClassName<'a> {
             fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-                if let Ok(class_name) = unsafe { msg_send![self.0.id, className] } {
-                    write_nsstring(class_name, f)
-                } else {
-                    f.write_str("<failed to get class name>")
-                }
+                autoreleasepool(|pool| {
+                    if let Ok(Some(class_name)) = unsafe { msg_send![self.0, className] } {
+                        write_nsstring(unsafe { pool.autoreleased(class_name) }, f)
+                    } else {
+                        f.write_str("<failed to get class name>")
+                    }
+                })
             }
         }
 
-        f.debug_tuple("Error").field(&PrintClassName(self)).finish()
+        match self.repr {
+            Repr::Exception(ref id) => f.debug_tuple("Error").field(&PrintClassName(id)).finish(),
+            Repr::MismatchedTypes {
+                sel,
+                ref expected,
+                ref found,
+            } => f
+                .debug_struct("MismatchedTypes")
+                .field("sel", &sel)
+                .field("expected", expected)
+                .field("found", found)
+                .finish(),
+            Repr::NilReturn(sel) => f.debug_tuple("NilReturn").field(&sel).finish(),
+        }
     }
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let id = match self.repr {
+            Repr::Exception(ref id) => id,
+            Repr::MismatchedTypes {
+                sel,
+                ref expected,
+                ref found,
+            } => {
+                return write!(
+                    f,
+                    "Method `{}` has type encoding `{}`, but the message was sent with `{}`",
+                    sel.name(),
+                    expected,
+                    found
+                );
+            }
+            Repr::NilReturn(sel) => {
+                return write!(
+                    f,
+                    "Method `{}` returned nil, but its return type cannot be nil; \
+                     use an `Option` to handle nil",
+                    sel.name()
+                );
+            }
+        };
+
         // try to load the classes we need
         if let Some(ns_exception) = optional_class!(NSException) {
-            if let Ok(true) = unsafe { msg_send![self.id, isKindOfClass: ns_exception] } {
-                if let Ok(reason) = unsafe { msg_send![self.id, reason] } {
-                    return write_nsstring(reason, f);
-                }
-
-                return f
-                    .write_str("<an error occurred while accessing the exception's properties>");
+            let is_exception = unsafe { msg_send![Bool => id, isKindOfClass: ns_exception] };
+            if let Ok(true) = is_exception.map(Bool::as_bool) {
+                return autoreleasepool(|pool| {
+                    if let Ok(Some(reason)) = unsafe { msg_send![id, reason] } {
+                        write_nsstring(unsafe { pool.autoreleased(reason) }, f)
+                    } else {
+                        f.write_str(
+                            "<an error occurred while accessing the exception's properties>",
+                        )
+                    }
+                });
             }
         }
 
@@ -95,13 +192,6 @@ impl fmt::Display for Error {
 #[cfg(feature = "std")]
 impl std::error::Error for Error {}
 
-impl Drop for Error {
-    fn drop(&mut self) {
-        // destroy the ID
-        let _ = unsafe { msg_send![() => self.id, release] };
-    }
-}
-
 /// Try to run a function that may cause an Objective C exception
 /// to be raised.
 ///
@@ -1,12 +1,144 @@
 // BSL 1.0 License
 
-use crate::cstr::c_char;
+use crate::{cstr::c_char, protocol::MethodDescription, Bool, Imp};
 
 #[link(name = "objc", kind = "dylib")]
 extern "C" {
     pub fn sel_registerName(name: *const c_char) -> *const ();
     pub fn sel_getName(sel: *const ()) -> *const c_char;
     pub fn objc_getClass(name: *const c_char) -> *const ();
+    pub fn objc_getProtocol(name: *const c_char) -> *const ();
+    pub fn objc_copyClassList(count: *mut libc::c_uint) -> *mut *const ();
+    pub fn objc_allocateClassPair(
+        superclass: *const (),
+        name: *const c_char,
+        extra_bytes: libc::size_t,
+    ) -> *const ();
+    pub fn objc_disposeClassPair(cls: *const ());
+    pub fn objc_registerClassPair(cls: *const ());
+    pub fn object_getClass(obj: *const ()) -> *const ();
+    pub fn object_setClass(obj: *const (), cls: *const ()) -> *const ();
+    // GNUstep takes the total size of the copy, while Apple only takes the
+    // bytes to add after the instance variables
+    #[cfg(not(target_vendor = "apple"))]
+    pub fn object_copy(obj: *const (), total_size: usize) -> *const ();
+    #[cfg(target_vendor = "apple")]
+    pub fn object_copy(obj: *const (), extra_bytes: usize) -> *const ();
+    pub fn object_dispose(obj: *const ()) -> *const ();
+    pub fn class_createInstance(cls: *const (), extra_bytes: usize) -> *const ();
+    pub fn objc_retain(obj: *const ()) -> *const ();
+    pub fn objc_release(obj: *const ());
+    pub fn objc_autorelease(obj: *const ()) -> *const ();
+    pub fn objc_autoreleasePoolPush() -> *mut ();
+    pub fn objc_autoreleasePoolPop(pool: *mut ());
+    pub fn objc_initWeak(location: *mut *const (), obj: *const ()) -> *const ();
+    pub fn objc_storeWeak(location: *mut *const (), obj: *const ()) -> *const ();
+    pub fn objc_loadWeakRetained(location: *mut *const ()) -> *const ();
+    pub fn objc_copyWeak(dest: *mut *const (), src: *mut *const ());
+    pub fn objc_destroyWeak(location: *mut *const ());
     pub fn class_getName(cls: *const ()) -> *const c_char;
     pub fn class_getSuperclass(cls: *const ()) -> *const ();
+    pub fn class_isMetaClass(cls: *const ()) -> Bool;
+    pub fn class_getInstanceSize(cls: *const ()) -> usize;
+    pub fn class_getVersion(cls: *const ()) -> libc::c_int;
+    pub fn class_setVersion(cls: *const (), version: libc::c_int);
+    pub fn class_respondsToSelector(cls: *const (), sel: *const ()) -> Bool;
+    pub fn class_getInstanceMethod(cls: *const (), name: *const ()) -> *const ();
+    pub fn class_getClassMethod(cls: *const (), name: *const ()) -> *const ();
+    pub fn class_copyMethodList(cls: *const (), count: *mut libc::c_uint) -> *mut *const ();
+    pub fn class_getInstanceVariable(cls: *const (), name: *const c_char) -> *const ();
+    pub fn class_copyIvarList(cls: *const (), count: *mut libc::c_uint) -> *mut *const ();
+    pub fn ivar_getName(ivar: *const ()) -> *const c_char;
+    pub fn ivar_getTypeEncoding(ivar: *const ()) -> *const c_char;
+    pub fn ivar_getOffset(ivar: *const ()) -> isize;
+    pub fn class_getProperty(cls: *const (), name: *const c_char) -> *const ();
+    pub fn class_copyPropertyList(cls: *const (), count: *mut libc::c_uint) -> *mut *const ();
+    pub fn property_getName(property: *const ()) -> *const c_char;
+    pub fn property_getAttributes(property: *const ()) -> *const c_char;
+    pub fn method_setImplementation(method: *const (), imp: Imp) -> Imp;
+    pub fn method_exchangeImplementations(first: *const (), second: *const ());
+    pub fn class_replaceMethod(
+        cls: *const (),
+        name: *const (),
+        imp: Imp,
+        types: *const c_char,
+    ) -> Option<Imp>;
+    pub fn method_getName(method: *const ()) -> *const ();
+    pub fn method_getImplementation(method: *const ()) -> Imp;
+    pub fn method_getTypeEncoding(method: *const ()) -> *const c_char;
+    pub fn method_getNumberOfArguments(method: *const ()) -> libc::c_uint;
+    pub fn method_copyReturnType(method: *const ()) -> *mut c_char;
+    pub fn method_copyArgumentType(method: *const (), index: libc::c_uint) -> *mut c_char;
+    pub fn class_addMethod(
+        cls: *const (),
+        name: *const (),
+        imp: Imp,
+        types: *const c_char,
+    ) -> Bool;
+    pub fn class_addIvar(
+        cls: *const (),
+        name: *const c_char,
+        size: libc::size_t,
+        alignment: u8,
+        types: *const c_char,
+    ) -> Bool;
+    pub fn class_addProtocol(cls: *const (), protocol: *const ()) -> Bool;
+    pub fn protocol_getName(protocol: *const ()) -> *const c_char;
+    pub fn objc_allocateProtocol(name: *const c_char) -> *const ();
+    pub fn objc_registerProtocol(protocol: *const ());
+    pub fn protocol_addMethodDescription(
+        protocol: *const (),
+        name: *const (),
+        types: *const c_char,
+        is_required: Bool,
+        is_instance_method: Bool,
+    );
+    pub fn protocol_addProtocol(protocol: *const (), addition: *const ());
+    pub fn protocol_addProperty(
+        protocol: *const (),
+        name: *const c_char,
+        attributes: *const PropertyAttribute,
+        attribute_count: libc::c_uint,
+        is_required: Bool,
+        is_instance_property: Bool,
+    );
+    pub fn protocol_conformsToProtocol(protocol: *const (), other: *const ()) -> Bool;
+    pub fn protocol_copyProtocolList(
+        protocol: *const (),
+        count: *mut libc::c_uint,
+    ) -> *mut *const ();
+    pub fn protocol_getMethodDescription(
+        protocol: *const (),
+        name: *const (),
+        is_required: Bool,
+        is_instance_method: Bool,
+    ) -> MethodDescription;
+    pub fn protocol_copyMethodDescriptionList(
+        protocol: *const (),
+        is_required: Bool,
+        is_instance_method: Bool,
+        count: *mut libc::c_uint,
+    ) -> *mut MethodDescription;
+    pub fn class_conformsToProtocol(cls: *const (), protocol: *const ()) -> Bool;
+    pub fn class_copyProtocolList(cls: *const (), count: *mut libc::c_uint) -> *mut *const ();
+    pub fn imp_implementationWithBlock(block: *const ()) -> Imp;
+    pub fn imp_getBlock(imp: Imp) -> *const ();
+    pub fn imp_removeBlock(imp: Imp) -> Bool;
+    pub fn _Block_copy(block: *const ()) -> *mut ();
+    pub fn _Block_release(block: *const ());
+
+    pub static _NSConcreteStackBlock: [*const (); 32];
+}
+
+#[cfg(feature = "testing")]
+#[link(name = "objc", kind = "dylib")]
+extern "C-unwind" {
+    pub fn objc_exception_throw(exception: *const ()) -> !;
+}
+
+/// The `objc_property_attribute_t` type.
+#[repr(C)]
+pub struct PropertyAttribute {
+    pub name: *const c_char,
+    pub value: *const c_char,
 }
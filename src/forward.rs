@@ -0,0 +1,182 @@
+// BSL 1.0 License
+
+//! Forwarding of messages that an object does not respond to.
+//!
+//! On Apple platforms, forwarding goes through
+//! `-forwardingTargetForSelector:`. On GNUstep, the runtime's
+//! `objc_proxy_lookup` and `__objc_msg_forward2` hooks are used instead.
+
+#[cfg(not(target_vendor = "apple"))]
+use crate::Imp;
+use crate::{ClassDecl, Object, RcBlock, Sel};
+use alloc::sync::Arc;
+
+/// What to do with a message that an object does not respond to.
+#[derive(Debug, Clone, Copy)]
+pub enum Forward {
+    /// Send the message to another object instead.
+    Target(Object),
+    /// Call the given implementation with the original receiver and
+    /// arguments.
+    ///
+    /// This is only available on GNUstep, whose runtime lets the
+    /// implementation of a message be chosen when it is forwarded.
+    #[cfg(not(target_vendor = "apple"))]
+    Implementation(Imp),
+    /// Fall back on the runtime's default behaviour, which usually raises
+    /// an exception.
+    Unhandled,
+}
+
+impl ClassDecl {
+    /// Forward messages that instances of the class do not respond to.
+    ///
+    /// `handler` is called with the receiver and the selector of each such
+    /// message, and decides how the message is forwarded. It may be called
+    /// more than once for the same message, and on any thread that sends
+    /// one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the class already has a `-forwardingTargetForSelector:`
+    /// method.
+    pub fn add_forwarding_handler<F>(&mut self, handler: F)
+    where
+        F: Fn(Object, Sel) -> Forward + Send + Sync + 'static,
+    {
+        let handler = Arc::new(handler);
+
+        let target_handler = handler.clone();
+        let target = RcBlock::new(move |receiver: Object, sel: Sel| -> Option<Object> {
+            match target_handler(receiver, sel) {
+                Forward::Target(target) => Some(target),
+                _ => None,
+            }
+        });
+        unsafe { self.add_block_method(sel!(forwardingTargetForSelector:), &target) };
+
+        #[cfg(not(target_vendor = "apple"))]
+        {
+            let implementation = RcBlock::new(move |receiver: Object, sel: Sel| -> *const () {
+                match handler(receiver, sel) {
+                    Forward::Implementation(imp) => imp as *const (),
+                    _ => core::ptr::null(),
+                }
+            });
+            unsafe {
+                self.add_block_method(
+                    sel!(loafForwardingImplementationForSelector:),
+                    &implementation,
+                );
+            }
+
+            hooks::install();
+        }
+    }
+}
+
+#[cfg(not(target_vendor = "apple"))]
+mod hooks {
+    use crate::{send_message, Imp, Object, Sel};
+    use core::{
+        hint, mem, ptr,
+        sync::atomic::{AtomicPtr, AtomicU8, Ordering::*},
+    };
+
+    type ProxyLookup = unsafe extern "C" fn(*const (), *const ()) -> *const ();
+    type MessageForward = unsafe extern "C" fn(*const (), *const ()) -> Option<Imp>;
+
+    #[link(name = "objc", kind = "dylib")]
+    extern "C" {
+        static mut objc_proxy_lookup: Option<ProxyLookup>;
+        static mut __objc_msg_forward2: Option<MessageForward>;
+    }
+
+    const UNINSTALLED: u8 = 0;
+    const INSTALLING: u8 = 1;
+    const INSTALLED: u8 = 2;
+
+    static STATE: AtomicU8 = AtomicU8::new(UNINSTALLED);
+    static PREVIOUS_PROXY_LOOKUP: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+    static PREVIOUS_MESSAGE_FORWARD: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+
+    /// Install the forwarding hooks, keeping the previous hooks to fall
+    /// back on.
+    pub(super) fn install() {
+        match STATE.compare_exchange(UNINSTALLED, INSTALLING, AcqRel, Acquire) {
+            Ok(_) => {}
+            Err(_) => {
+                while STATE.load(Acquire) != INSTALLED {
+                    hint::spin_loop();
+                }
+                return;
+            }
+        }
+
+        unsafe {
+            let previous = ptr::addr_of_mut!(objc_proxy_lookup).replace(Some(proxy_lookup));
+            PREVIOUS_PROXY_LOOKUP
+                .store(previous.map_or(ptr::null_mut(), |f| f as *mut ()), Release);
+
+            let previous = ptr::addr_of_mut!(__objc_msg_forward2).replace(Some(message_forward));
+            PREVIOUS_MESSAGE_FORWARD
+                .store(previous.map_or(ptr::null_mut(), |f| f as *mut ()), Release);
+        }
+
+        STATE.store(INSTALLED, Release);
+    }
+
+    /// Get the receiver as an object, if it was declared with a forwarding
+    /// handler.
+    unsafe fn forwarding_object(receiver: *const ()) -> Option<Object> {
+        if receiver.is_null() {
+            return None;
+        }
+
+        let obj = Object::from_ptr(receiver);
+        obj.responds_to(sel!(loafForwardingImplementationForSelector:))
+            .then_some(obj)
+    }
+
+    unsafe extern "C" fn proxy_lookup(receiver: *const (), sel: *const ()) -> *const () {
+        if let Some(obj) = forwarding_object(receiver) {
+            let sel = Sel::from_ptr(sel);
+            let target: Option<Object> =
+                send_message(obj, sel!(forwardingTargetForSelector:), (sel,), false)
+                    .ok()
+                    .flatten();
+            return target.map_or(ptr::null(), |target| target.as_ptr());
+        }
+
+        let previous = PREVIOUS_PROXY_LOOKUP.load(Acquire);
+        if previous.is_null() {
+            ptr::null()
+        } else {
+            let previous: ProxyLookup = mem::transmute(previous);
+            previous(receiver, sel)
+        }
+    }
+
+    unsafe extern "C" fn message_forward(receiver: *const (), sel: *const ()) -> Option<Imp> {
+        if let Some(obj) = forwarding_object(receiver) {
+            let imp: *const () = send_message(
+                obj,
+                sel!(loafForwardingImplementationForSelector:),
+                (Sel::from_ptr(sel),),
+                false,
+            )
+            .unwrap_or(ptr::null());
+            if !imp.is_null() {
+                return Some(mem::transmute::<*const (), Imp>(imp));
+            }
+        }
+
+        let previous = PREVIOUS_MESSAGE_FORWARD.load(Acquire);
+        if previous.is_null() {
+            None
+        } else {
+            let previous: MessageForward = mem::transmute(previous);
+            previous(receiver, sel)
+        }
+    }
+}
@@ -0,0 +1,124 @@
+// BSL 1.0 License
+
+//! Instance variables of Objective-C classes.
+
+use crate::{
+    cstr::{runtime_str, CStr},
+    ffi, Class, Encode, List, Object,
+};
+use core::fmt;
+
+opaque_type! {
+    #[doc = "An instance variable belonging to an Objective-C class."]
+    Ivar
+}
+
+impl fmt::Debug for Ivar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Ivar")
+            .field("name", &self.name())
+            .field("type_encoding", &self.type_encoding())
+            .field("offset", &self.offset())
+            .finish()
+    }
+}
+
+impl Ivar {
+    /// Get the name of this instance variable.
+    pub fn name(&self) -> &str {
+        let c_ptr = unsafe { ffi::ivar_getName(self.as_ptr()) };
+        unsafe { runtime_str(c_ptr) }
+    }
+
+    /// Get the type encoding of this instance variable.
+    pub fn type_encoding(&self) -> &str {
+        let c_ptr = unsafe { ffi::ivar_getTypeEncoding(self.as_ptr()) };
+        unsafe { runtime_str(c_ptr) }
+    }
+
+    /// Get the offset of this instance variable from the start of the
+    /// object, in bytes.
+    pub fn offset(&self) -> isize {
+        unsafe { ffi::ivar_getOffset(self.as_ptr()) }
+    }
+}
+
+impl Class {
+    /// Get the instance variable of this class with the given name.
+    ///
+    /// This also searches the superclasses of this class.
+    pub fn instance_variable(&self, name: &CStr) -> Option<Ivar> {
+        let ptr = unsafe { ffi::class_getInstanceVariable(self.as_ptr(), name.as_ptr()) };
+        if ptr.is_null() {
+            None
+        } else {
+            Some(unsafe { Ivar::from_ptr(ptr) })
+        }
+    }
+
+    /// Iterate over the instance variables declared by this class.
+    ///
+    /// Instance variables declared by superclasses are not included.
+    pub fn instance_variables(&self) -> List<Ivar> {
+        let mut count = 0;
+        unsafe {
+            let ivars = ffi::class_copyIvarList(self.as_ptr(), &mut count);
+            List::from_raw(ivars as *mut Ivar, count as usize)
+        }
+    }
+}
+
+impl Object {
+    /// Get a reference to the instance variable with the given name.
+    ///
+    /// # Safety
+    ///
+    /// The object must be valid, and the instance variable must not be
+    /// mutated while the reference is alive.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the object's class has no instance variable with the
+    /// given name, or if its type encoding does not match `T`.
+    pub unsafe fn ivar<T: Encode>(&self, name: &CStr) -> &T {
+        &*self.ivar_ptr::<T>(name)
+    }
+
+    /// Get a mutable reference to the instance variable with the given
+    /// name.
+    ///
+    /// # Safety
+    ///
+    /// The object must be valid, and `T` must be the actual type of the
+    /// instance variable, not only a type with the same encoding. Since
+    /// `Object` is a copyable handle, borrowing it does not prevent
+    /// aliasing: the instance variable must not be read or written through
+    /// any other reference, handle or message while the returned reference
+    /// is alive.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the object's class has no instance variable with the
+    /// given name, or if its type encoding does not match `T`.
+    #[allow(clippy::mut_from_ref)]
+    pub unsafe fn ivar_mut<T: Encode>(&self, name: &CStr) -> &mut T {
+        &mut *self.ivar_ptr::<T>(name)
+    }
+
+    unsafe fn ivar_ptr<T: Encode>(&self, name: &CStr) -> *mut T {
+        let cls = self.class();
+        let ivar = cls
+            .instance_variable(name)
+            .unwrap_or_else(|| panic!("{:?} has no instance variable {:?}", cls, name));
+
+        assert!(
+            T::ENCODING.equivalent_to_str(ivar.type_encoding()),
+            "Instance variable {:?} has type encoding `{}`, but was accessed as `{}`",
+            name,
+            ivar.type_encoding(),
+            T::ENCODING,
+        );
+
+        (self.as_ptr() as *mut u8).offset(ivar.offset()) as *mut T
+    }
+}
@@ -24,6 +24,8 @@
 #![no_std]
 #![deprecated = "Use the `objc2` crate instead"]
 
+extern crate alloc;
+
 #[cfg(feature = "std")]
 extern crate std;
 
@@ -32,27 +34,61 @@ mod macros;
 
 pub(crate) mod ffi;
 
+mod autorelease;
+pub use autorelease::{autoreleasepool, AutoreleasePool, Autoreleased};
+
+mod bool;
+pub use crate::bool::Bool;
+
+mod block;
+pub use block::{remove_block_imp, Block, BlockArguments, BlockClosure, RcBlock, StackBlock};
+
 mod class;
-pub use class::Class;
+pub use class::{Ancestors, Class};
 
 pub(crate) mod cstr;
 
+mod declare;
+pub use declare::{ClassDecl, MethodImplementation, ProtocolDecl};
+
+mod encode;
+pub use encode::{Encode, EncodeArgument, EncodeArguments, Encoding};
+
 mod error;
 pub use error::{Error, Result};
 
+mod forward;
+pub use forward::Forward;
+
+mod ivar;
+pub use ivar::Ivar;
+
+mod list;
+pub use list::List;
+
 mod message;
 pub use message::{send_message, send_super_message, MessageArguments, MessageTarget};
 
+mod method;
+pub use method::{ExchangeGuard, Imp, ImplementationGuard, Method};
+
+mod object;
+pub use object::{id, Object};
+
+mod property;
+pub use property::{Property, PropertyAttributes, SetterSemantics};
+
+mod protocol;
+pub use protocol::{MethodDescription, Protocol};
+
+mod rc;
+pub use rc::{Id, ObjectType, WeakId};
+
 mod sel;
 pub use sel::Sel;
 
-opaque_type! {
-    #[doc = "A pointer to an Objective C object."]
-    Object
-}
-
-#[allow(non_camel_case_types)]
-pub type id = Object;
+#[cfg(feature = "testing")]
+pub mod testing;
 
 /// Private types used in macros.
 /// 
@@ -60,7 +96,7 @@ pub type id = Object;
 /// exempt.
 #[doc(hidden)]
 pub mod __private {
-    pub use crate::{class::AtomicClass, sel::AtomicSel};
+    pub use crate::{class::AtomicClass, protocol::AtomicProtocol, sel::AtomicSel};
 
     pub use cstr_core::cstr; 
 }
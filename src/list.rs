@@ -0,0 +1,75 @@
+// BSL 1.0 License
+
+//! Lists that have been copied out of the runtime.
+
+use core::{fmt, iter::FusedIterator, ptr::NonNull};
+
+/// An iterator over a list that was copied out of the runtime, such as by
+/// `class_copyMethodList`.
+///
+/// The list is freed once the iterator is dropped.
+pub struct List<T: Copy> {
+    ptr: Option<NonNull<T>>,
+    len: usize,
+    index: usize,
+}
+
+impl<T: Copy> List<T> {
+    /// Create a list from a pointer returned by the runtime.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must either be null, or point to `len` valid elements that
+    /// were allocated with `malloc`.
+    pub(crate) unsafe fn from_raw(ptr: *mut T, len: usize) -> List<T> {
+        let ptr = NonNull::new(ptr);
+        List {
+            len: if ptr.is_some() { len } else { 0 },
+            ptr,
+            index: 0,
+        }
+    }
+
+    /// Get the remaining elements of the list as a slice.
+    pub fn as_slice(&self) -> &[T] {
+        match self.ptr {
+            Some(ptr) => unsafe {
+                core::slice::from_raw_parts(ptr.as_ptr().add(self.index), self.len - self.index)
+            },
+            None => &[],
+        }
+    }
+}
+
+impl<T: Copy> Iterator for List<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let item = *self.as_slice().first()?;
+        self.index += 1;
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len - self.index;
+        (len, Some(len))
+    }
+}
+
+impl<T: Copy> ExactSizeIterator for List<T> {}
+
+impl<T: Copy> FusedIterator for List<T> {}
+
+impl<T: Copy + fmt::Debug> fmt::Debug for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.as_slice()).finish()
+    }
+}
+
+impl<T: Copy> Drop for List<T> {
+    fn drop(&mut self) {
+        if let Some(ptr) = self.ptr {
+            unsafe { libc::free(ptr.as_ptr() as *mut libc::c_void) };
+        }
+    }
+}
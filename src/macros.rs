@@ -38,6 +38,12 @@ macro_rules! opaque_type {
             ptr: core::sync::atomic::AtomicPtr<()>,
         }
 
+        impl Default for $aident {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
         impl $aident {
             /// Create a new, empty atomic holder.
             pub const fn new() -> Self {
@@ -85,7 +91,7 @@ macro_rules! sel {
         $crate::sel!(@raw_str stringify!($name))
     };
     ($($name: ident :)+) => {
-        $crate::sel!(@raw_str concat!($(stringify!($name), ":")+,))
+        $crate::sel!(@raw_str concat!($(stringify!($name), ":",)+))
     };
     (@raw_str $name: expr) => {{
         static CACHED: $crate::__private::AtomicSel =
@@ -113,10 +119,36 @@ macro_rules! optional_class {
 #[macro_export]
 macro_rules! class {
     ($name: ident) => {{
-        match $crate::optional_class($name) {
+        match $crate::optional_class!($name) {
             Some(cls) => cls,
             None => {
-                panic!("Could not find class `{}`", $name);
+                panic!("Could not find class `{}`", stringify!($name));
+            }
+        }
+    }};
+}
+
+/// Fetch a protocol, optionally.
+#[macro_export]
+macro_rules! optional_protocol {
+    ($name: ident) => {{
+        static CACHED: $crate::__private::AtomicProtocol =
+            $crate::__private::AtomicProtocol::new();
+        CACHED.try_get_or_init(|| {
+            let name = $crate::__private::cstr!(stringify!($name));
+            $crate::Protocol::new(name)
+        })
+    }};
+}
+
+/// Fetch a protocol.
+#[macro_export]
+macro_rules! protocol {
+    ($name: ident) => {{
+        match $crate::optional_protocol!($name) {
+            Some(protocol) => protocol,
+            None => {
+                panic!("Could not find protocol `{}`", stringify!($name));
             }
         }
     }};
@@ -141,20 +173,46 @@ macro_rules! msg_send {
             $checked
         )
     }};
-    (@expl_checked $checked: expr, $ty: ty => $obj: expr, $($args: tt)*) => {
+    (@make_super_fncall $ty: ty, $obj: expr, $sclass: expr, $checked: expr, $name: ident) => {{
+        $crate::send_super_message::<_, $ty, _>(
+            $obj,
+            $sclass,
+            $crate::sel!($name),
+            (),
+            $checked
+        )
+    }};
+    (@make_super_fncall $ty: ty, $obj: expr, $sclass: expr, $checked: expr,
+        $($name: ident : $arg: expr)+) => {{
+        $crate::send_super_message::<_, $ty, _>(
+            $obj,
+            $sclass,
+            $crate::sel!($($name:)+),
+            ($($arg,)*),
+            $checked
+        )
+    }};
+    // these need to come first, since `super(...)` is also an expression
+    (@expl_checked $checked: expr, super($obj: expr, $sclass: expr), $($args: tt)*) => {
         $crate::msg_send!(
-            @make_fncall $ty,
-            send_message,
+            @expl_checked $checked,
+            _ => super($obj, $sclass), $($args)*
+        )
+    };
+    (@expl_checked $checked: expr, $ty: ty =>
+        super($obj: expr, $sclass: expr), $($args: tt)*) => {
+        $crate::msg_send!(
+            @make_super_fncall $ty,
             $obj,
+            $sclass,
             $checked,
             $($args)*
         )
     };
-    (@expl_checked $checked: expr, $ty: ty =>
-        super($obj: expr, $sclass: expr), $($args: tt)*) => {
+    (@expl_checked $checked: expr, $ty: ty => $obj: expr, $($args: tt)*) => {
         $crate::msg_send!(
             @make_fncall $ty,
-            send_super_message,
+            send_message,
             $obj,
             $checked,
             $($args)*
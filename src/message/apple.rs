@@ -2,6 +2,10 @@
 
 // Binding to Apple Objective C functionality vary from platform to platform.
 
+use super::Superclass;
+use crate::Sel;
+use core::any::Any;
+
 mod arch {
     use core::{
         any::{Any, TypeId},
@@ -134,7 +138,7 @@ mod arch {
 }
 
 pub(crate) unsafe fn send_message_function<R: Any>(
-    _receiver: *const (),
+    _receiver: &mut *mut (),
     _sel: Sel,
 ) -> unsafe extern "C" fn() {
     arch::msg_function::<R>()
@@ -146,3 +150,9 @@ pub(crate) unsafe fn send_super_message_function<R: Any>(
 ) -> unsafe extern "C" fn() {
     arch::super_function::<R>()
 }
+
+/// `objc_msgSendSuper` is called with a pointer to the `objc_super`
+/// structure, and finds the receiver from there.
+pub(crate) fn super_message_receiver(superclass: &Superclass) -> *mut () {
+    superclass as *const Superclass as *mut ()
+}
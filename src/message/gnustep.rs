@@ -1,25 +1,49 @@
 // BSL 1.0 License
 
 use super::Superclass;
-use crate::Sel;
-use core::any::Any;
+use crate::{cstr::c_char, Sel};
+use core::{any::Any, ptr};
+
+/// The slot returned by `objc_msg_lookup_sender`.
+#[repr(C)]
+struct Slot {
+    owner: *const (),
+    cached_for: *const (),
+    types: *const c_char,
+    version: libc::c_int,
+    method: unsafe extern "C" fn(),
+    selector: *const (),
+}
 
 #[link(name = "objc", kind = "dylib")]
 extern "C" {
-    fn objc_msg_lookup(receiver: *const (), op: *const ()) -> unsafe extern "C" fn();
+    fn objc_msg_lookup_sender(
+        receiver: *mut *mut (),
+        op: *const (),
+        sender: *const (),
+    ) -> *const Slot;
     fn objc_msg_lookup_super(sup: *const Superclass, sel: *const ()) -> unsafe extern "C" fn();
 }
 
+/// Unlike `objc_msg_lookup`, this lets the runtime replace the receiver
+/// through the `objc_proxy_lookup` hook.
+#[allow(clippy::extra_unused_type_parameters)]
 pub(crate) unsafe fn send_message_function<R: Any>(
-    receiver: *const (),
+    receiver: &mut *mut (),
     sel: Sel,
 ) -> unsafe extern "C" fn() {
-    objc_msg_lookup(receiver, sel.as_ptr())
+    (*objc_msg_lookup_sender(receiver, sel.as_ptr(), ptr::null())).method
 }
 
+#[allow(clippy::extra_unused_type_parameters)]
 pub(crate) unsafe fn send_super_message_function<R: Any>(
     receiver: &Superclass,
     sel: Sel,
 ) -> unsafe extern "C" fn() {
     objc_msg_lookup_super(receiver, sel.as_ptr())
 }
+
+/// The IMP returned by the lookup is called with the receiver itself.
+pub(crate) fn super_message_receiver(superclass: &Superclass) -> *mut () {
+    superclass.receiver
+}
@@ -1,8 +1,14 @@
 // BSL 1.0 License
 
-use crate::{class::Class, sel::Sel, Object, Result};
-use __private::Sealed;
-use core::{any::Any, mem, ptr::null_mut};
+use crate::{
+    class::Class,
+    encode::{Encode, EncodeArgument, Encoding},
+    ffi,
+    sel::Sel,
+    Object, Result,
+};
+pub(crate) use __private::Sealed;
+use core::{any::Any, mem, mem::MaybeUninit, ptr::null_mut};
 
 cfg_if::cfg_if! {
     if #[cfg(target_vendor = "apple")] {
@@ -14,6 +20,9 @@ cfg_if::cfg_if! {
     }
 }
 
+#[cfg(any(debug_assertions, feature = "verify_message"))]
+mod verify;
+
 /// An object that a message can be sent to.
 ///
 /// # Safety
@@ -46,10 +55,13 @@ unsafe impl MessageTarget for Option<Object> {
     }
 }
 
-unsafe impl MessageTarget for &Superclass {
+/// A raw pointer that a message is sent to.
+#[derive(Copy, Clone)]
+struct RawTarget(*mut ());
+
+unsafe impl MessageTarget for RawTarget {
     fn ptr(self) -> *mut () {
-        let p: *const () = self as *const Superclass as *const ();
-        p as *mut ()
+        self.0
     }
 }
 
@@ -60,6 +72,9 @@ unsafe impl MessageTarget for &Superclass {
 /// This should only really be implemented for tuple types. It has
 /// a `Sealed` marker for this reason.
 pub unsafe trait MessageArguments: Sealed + Sized {
+    /// The type encodings of the arguments.
+    const ENCODINGS: &'static [Encoding<'static>];
+
     /// Call the given messaging function with this as an argument.
     ///
     /// # Safety
@@ -78,7 +93,9 @@ macro_rules! message_arguments {
         impl<$($ident),*> Sealed for ($($ident),* $($tt)*) {}
 
         #[allow(non_snake_case, unused_parens)]
-        unsafe impl<$($ident),*> MessageArguments for ($($ident),* $($tt)*) {
+        unsafe impl<$($ident: EncodeArgument),*> MessageArguments for ($($ident),* $($tt)*) {
+            const ENCODINGS: &'static [Encoding<'static>] = &[$($ident::ENCODING),*];
+
             #[inline]
             unsafe fn call_message<
                 Target: MessageTarget,
@@ -90,12 +107,15 @@ macro_rules! message_arguments {
                 sel: Sel,
             ) -> Return {
                 // transmute the ptr to a function pointer
-                let function_ptr: unsafe extern "C" fn(*const (), *const (), $($ident),*) -> Return =
-                    mem::transmute(function_ptr);
+                let function_ptr: unsafe extern "C" fn(
+                    *const (),
+                    *const (),
+                    $($ident::Raw),*
+                ) -> Return = mem::transmute(function_ptr);
 
                 // disassemble the tuple and call the function
                 let ($($ident),* $($tt)*) = self;
-                function_ptr(target.ptr(), sel.as_ptr(), $($ident),*)
+                function_ptr(target.ptr(), sel.as_ptr(), $($ident.into_raw()),*)
             }
         }
     };
@@ -114,32 +134,63 @@ message_arguments! {
 
 /// Try to send a message.
 ///
+/// In debug builds, or when the `verify_message` feature is enabled, the
+/// types of `Return` and `Arguments` are checked against the type
+/// encoding of the method that receives the message. If they do not
+/// match, an error is returned instead of sending the message.
+///
+/// Messages that may return nil should use an `Option` return type, such
+/// as `Option<Object>`, where nil is mapped to `None`. In debug builds, a
+/// nil return for a type that cannot be nil results in an error.
+///
 /// # Safety
 ///
 /// The message must be valid for the given target.
-pub unsafe fn send_message<Target: MessageTarget, Return: Any, Arguments: MessageArguments>(
+pub unsafe fn send_message<
+    Target: MessageTarget,
+    Return: Any + Encode,
+    Arguments: MessageArguments,
+>(
     target: Target,
     sel: Sel,
     arguments: Arguments,
     checked: bool,
 ) -> Result<Return> {
-    let fn_ptr = platform::send_message_function::<Return>(target.ptr(), sel);
+    #[cfg(any(debug_assertions, feature = "verify_message"))]
+    {
+        let receiver = target.ptr();
+        if !receiver.is_null() {
+            let cls = Class::from_ptr(ffi::object_getClass(receiver));
+            verify::verify_message_signature(cls, sel, &Return::ENCODING, Arguments::ENCODINGS)?;
+        }
+    }
+
+    // the lookup may replace the receiver, e.g. for forwarding proxies
+    let mut receiver = target.ptr();
+    let fn_ptr = platform::send_message_function::<Return>(&mut receiver, sel);
+    let target = RawTarget(receiver);
 
-    if checked || cfg!(debug_assertions) {
-        crate::error::error_catcher(move || arguments.call_message(fn_ptr, target, sel))
+    let value = if checked || cfg!(debug_assertions) {
+        crate::error::error_catcher(move || arguments.call_message(fn_ptr, target, sel))?
     } else {
-        Ok(arguments.call_message(fn_ptr, target, sel))
-    }
+        arguments.call_message(fn_ptr, target, sel)
+    };
+
+    return_value(sel, value)
 }
 
 /// Send a message to the object's superclass.
 ///
+/// If the target is a class, the message is sent to the class method of
+/// the superclass instead. The types are verified in the same way as for
+/// [`send_message`].
+///
 /// # Safety
 ///
 /// The message must be valid for the given target.
 pub unsafe fn send_super_message<
     Target: MessageTarget,
-    Return: Any,
+    Return: Any + Encode,
     Arguments: MessageArguments,
 >(
     target: Target,
@@ -148,24 +199,83 @@ pub unsafe fn send_super_message<
     arguments: Arguments,
     checked: bool,
 ) -> Result<Return> {
+    let receiver = target.ptr();
+
+    // class methods are looked up on the metaclass of the superclass
+    let superclass = if !receiver.is_null()
+        && Class::from_ptr(ffi::object_getClass(receiver)).is_metaclass()
+        && !superclass.is_metaclass()
+    {
+        superclass.metaclass()
+    } else {
+        superclass
+    };
+
+    #[cfg(any(debug_assertions, feature = "verify_message"))]
+    verify::verify_message_signature(superclass, sel, &Return::ENCODING, Arguments::ENCODINGS)?;
+
     let superclass = Superclass {
-        receiver: target.ptr(),
+        receiver,
         superclass,
     };
     let fn_ptr = platform::send_super_message_function::<Return>(&superclass, sel);
 
-    if checked || cfg!(debug_assertions) {
-        crate::error::error_catcher(move || arguments.call_message(fn_ptr, &superclass, sel))
+    let value = if checked || cfg!(debug_assertions) {
+        crate::error::error_catcher(move || {
+            let target = RawTarget(platform::super_message_receiver(&superclass));
+            arguments.call_message(fn_ptr, target, sel)
+        })?
     } else {
-        Ok(arguments.call_message(fn_ptr, &superclass, sel))
+        let target = RawTarget(platform::super_message_receiver(&superclass));
+        arguments.call_message(fn_ptr, target, sel)
+    };
+
+    return_value(sel, value)
+}
+
+/// Convert the value returned by a message into its real type.
+///
+/// The value is returned as a `MaybeUninit` so that a nil pointer is never
+/// turned into a type that cannot be nil.
+#[allow(unused_variables)]
+unsafe fn return_value<Return: Encode>(sel: Sel, value: MaybeUninit<Return>) -> Result<Return> {
+    #[cfg(any(debug_assertions, feature = "verify_message"))]
+    if verify::is_non_null_pointer::<Return>()
+        && value.as_ptr().cast::<*const ()>().read().is_null()
+    {
+        return Err(crate::Error::nil_return(sel));
     }
+
+    if Return::OWNS_OBJECT && !returns_retained(sel.name()) {
+        ffi::objc_retain(value.as_ptr().cast::<*const ()>().read());
+    }
+
+    Ok(value.assume_init())
+}
+
+/// Tell whether a method returns objects that are already retained, going
+/// by the naming conventions for the selector.
+///
+/// These are the methods in the `alloc`, `new`, `copy`, `mutableCopy` and
+/// `init` families, whose names start with the family name, optionally
+/// preceded by underscores and followed by anything but a lowercase letter.
+fn returns_retained(name: &str) -> bool {
+    const FAMILIES: &[&str] = &["alloc", "new", "copy", "mutableCopy", "init"];
+
+    let name = name.trim_start_matches('_');
+    FAMILIES
+        .iter()
+        .any(|family| match name.strip_prefix(family) {
+            Some(rest) => !rest.starts_with(|c: char| c.is_ascii_lowercase()),
+            None => false,
+        })
 }
 
 /// Representation of the super-class.
 #[derive(Copy, Clone)]
 #[repr(C)]
 pub(crate) struct Superclass {
-    pub receiver: *const (),
+    pub receiver: *mut (),
     pub superclass: Class,
 }
 
@@ -175,3 +285,43 @@ mod __private {
         fn __sealed_marker() {}
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::returns_retained;
+
+    #[test]
+    fn ownership_families() {
+        for name in [
+            "alloc",
+            "new",
+            "newWithValue:",
+            "copy",
+            "copyWithZone:",
+            "mutableCopy",
+        ] {
+            assert!(returns_retained(name), "{}", name);
+        }
+        assert!(returns_retained("init"));
+        assert!(returns_retained("initWithFrame:"));
+        assert!(returns_retained("_init"));
+        assert!(returns_retained("__copy"));
+        assert!(returns_retained("new2"));
+    }
+
+    #[test]
+    fn other_selectors() {
+        for name in [
+            "description",
+            "newsletter",
+            "copyright",
+            "initialize",
+            "allocator",
+        ] {
+            assert!(!returns_retained(name), "{}", name);
+        }
+        assert!(!returns_retained("mutableCopying"));
+        assert!(!returns_retained("getCopy"));
+        assert!(!returns_retained(""));
+    }
+}
@@ -0,0 +1,66 @@
+// BSL 1.0 License
+
+//! Verification of message signatures against the runtime.
+
+use crate::{
+    cstr::CStr,
+    encode::{signature_matches, Encode, Encoding},
+    ffi, Class, Error, Result, Sel,
+};
+use alloc::string::String;
+use core::{fmt::Write, mem};
+
+/// Check that the types used to send a message match the type encoding
+/// of the method that will receive it.
+///
+/// Methods that cannot be found are not checked, as they may be handled
+/// dynamically through forwarding.
+pub(crate) fn verify_message_signature(
+    cls: Class,
+    sel: Sel,
+    ret: &Encoding<'_>,
+    args: &[Encoding<'_>],
+) -> Result {
+    let method = unsafe { ffi::class_getInstanceMethod(cls.as_ptr(), sel.as_ptr()) };
+    if method.is_null() {
+        return Ok(());
+    }
+
+    let types = unsafe { CStr::from_ptr(ffi::method_getTypeEncoding(method)) };
+    let types = match types.to_str() {
+        Ok(types) => types,
+        Err(_) => return Ok(()),
+    };
+
+    if signature_matches(types, ret, args) {
+        return Ok(());
+    }
+
+    let mut found = String::new();
+    write!(found, "{}@:", ret).expect("Writing to a String should never fail");
+    for arg in args {
+        write!(found, "{}", arg).expect("Writing to a String should never fail");
+    }
+
+    Err(Error::mismatched_types(sel, types.into(), found))
+}
+
+/// Tell whether a type is a pointer that can never be nil.
+///
+/// These are pointers where `Option` uses nil to represent `None`, such as
+/// `Object` or `Id<T>`.
+pub(crate) fn is_non_null_pointer<Return: Encode>() -> bool {
+    let is_pointer = matches!(
+        Return::ENCODING,
+        Encoding::Object
+            | Encoding::Block
+            | Encoding::Class
+            | Encoding::Sel
+            | Encoding::String
+            | Encoding::Pointer(_)
+    );
+
+    is_pointer
+        && mem::size_of::<Return>() == mem::size_of::<*const ()>()
+        && mem::size_of::<Option<Return>>() == mem::size_of::<Return>()
+}
@@ -0,0 +1,284 @@
+// BSL 1.0 License
+
+//! Types for representing methods and their implementations.
+
+use crate::{
+    cstr::{c_char, CStr},
+    encode, ffi, Class, Error, MethodImplementation, Result, Sel,
+};
+use alloc::string::String;
+use core::{fmt, marker::PhantomData, str};
+
+/// A pointer to the function that implements a method.
+pub type Imp = unsafe extern "C" fn();
+
+opaque_type! {
+    #[doc = "A method belonging to an Objective-C class."]
+    Method
+}
+
+impl fmt::Debug for Method {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Method")
+            .field("name", &self.name())
+            .field("type_encoding", &self.type_encoding())
+            .finish()
+    }
+}
+
+impl Method {
+    /// Get the selector of this method.
+    pub fn name(&self) -> Sel {
+        unsafe { Sel::from_ptr(ffi::method_getName(self.as_ptr())) }
+    }
+
+    /// Get the function that implements this method.
+    pub fn implementation(&self) -> Imp {
+        unsafe { ffi::method_getImplementation(self.as_ptr()) }
+    }
+
+    /// Get the type encoding of this method.
+    pub fn type_encoding(&self) -> &str {
+        let c_ptr = unsafe { ffi::method_getTypeEncoding(self.as_ptr()) };
+        let c_str = unsafe { CStr::from_ptr(c_ptr) };
+
+        str::from_utf8(c_str.to_bytes()).expect("Type encodings should be valid UTF-8")
+    }
+
+    /// Get the number of arguments that this method takes, including the
+    /// receiver and the selector.
+    pub fn arguments_count(&self) -> usize {
+        unsafe { ffi::method_getNumberOfArguments(self.as_ptr()) as usize }
+    }
+
+    /// Get the type encoding of the return type of this method.
+    pub fn return_type(&self) -> String {
+        let c_ptr = unsafe { ffi::method_copyReturnType(self.as_ptr()) };
+        unsafe { copied_string(c_ptr) }.expect("Methods should always have a return type")
+    }
+
+    /// Get the type encoding of the argument at the given index, where the
+    /// receiver and the selector are the first two arguments.
+    ///
+    /// Returns `None` if the method does not have an argument at `index`.
+    pub fn argument_type(&self, index: usize) -> Option<String> {
+        let index = index.try_into().ok()?;
+        let c_ptr = unsafe { ffi::method_copyArgumentType(self.as_ptr(), index) };
+        unsafe { copied_string(c_ptr) }
+    }
+}
+
+impl Method {
+    /// Set the function that implements this method, returning the
+    /// previous implementation.
+    ///
+    /// # Safety
+    ///
+    /// `imp` must have the signature described by the type encoding of
+    /// this method.
+    pub unsafe fn set_implementation(&self, imp: Imp) -> Imp {
+        ffi::method_setImplementation(self.as_ptr(), imp)
+    }
+
+    /// Exchange the implementations of this method and another method.
+    ///
+    /// # Safety
+    ///
+    /// Both methods must have compatible signatures.
+    pub unsafe fn exchange_implementations(&self, other: Method) {
+        ffi::method_exchangeImplementations(self.as_ptr(), other.as_ptr())
+    }
+
+    /// Replace the implementation of this method until the returned guard
+    /// is dropped.
+    ///
+    /// `replacement` is given the original implementation and returns the
+    /// new one. It runs before the method is changed, so the original can
+    /// be stored where the new implementation will find it, e.g. in a
+    /// static, before the new implementation can be called. The signature
+    /// of the new implementation is checked against the type encoding of
+    /// this method.
+    ///
+    /// # Safety
+    ///
+    /// The new implementation must behave correctly for every receiver of
+    /// this method.
+    pub unsafe fn replace_implementation<F, R>(
+        &self,
+        replacement: R,
+    ) -> Result<ImplementationGuard<F>>
+    where
+        F: MethodImplementation,
+        R: FnOnce(F) -> F,
+    {
+        check_signature::<F>(*self)?;
+
+        let imp = replacement(F::from_imp(self.implementation()));
+        let original = self.set_implementation(imp.imp());
+        Ok(ImplementationGuard {
+            method: *self,
+            original,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Exchange the implementations of this method and another method until
+    /// the returned guard is dropped.
+    ///
+    /// Returns an error if the methods have different type encodings.
+    ///
+    /// # Safety
+    ///
+    /// Each implementation must behave correctly for every receiver of the
+    /// other method.
+    pub unsafe fn exchange(&self, other: Method) -> Result<ExchangeGuard> {
+        let same_types = self.arguments_count() == other.arguments_count()
+            && self.return_type() == other.return_type()
+            && (0..self.arguments_count()).all(|i| self.argument_type(i) == other.argument_type(i));
+        if !same_types {
+            return Err(Error::mismatched_types(
+                other.name(),
+                self.type_encoding().into(),
+                other.type_encoding().into(),
+            ));
+        }
+
+        self.exchange_implementations(other);
+        Ok(ExchangeGuard {
+            first: *self,
+            second: other,
+        })
+    }
+}
+
+impl Class {
+    /// Replace the implementation of an instance method of this class until
+    /// the returned guard is dropped.
+    ///
+    /// `replacement` is given the original implementation and returns the
+    /// new one, as with [`Method::replace_implementation`]. If the method
+    /// is inherited from a superclass, it is added to this class so that
+    /// the superclass is left unchanged. Once the guard is dropped, the
+    /// added method calls the inherited implementation. To replace a class
+    /// method, call this on the [metaclass](Class::metaclass).
+    ///
+    /// # Safety
+    ///
+    /// The new implementation must behave correctly for every receiver of
+    /// this method.
+    ///
+    /// # Panics
+    ///
+    /// Panics if neither this class nor its superclasses implement the
+    /// method.
+    pub unsafe fn replace_method<F, R>(
+        &self,
+        sel: Sel,
+        replacement: R,
+    ) -> Result<ImplementationGuard<F>>
+    where
+        F: MethodImplementation,
+        R: FnOnce(F) -> F,
+    {
+        let inherited = self
+            .instance_method(sel)
+            .unwrap_or_else(|| panic!("{:?} does not implement {:?}", self, sel));
+        check_signature::<F>(inherited)?;
+
+        // the nearest method is the one that instances of this class use
+        let original = inherited.implementation();
+        let imp = replacement(F::from_imp(original));
+
+        let types = ffi::method_getTypeEncoding(inherited.as_ptr());
+        let original = ffi::class_replaceMethod(self.as_ptr(), sel.as_ptr(), imp.imp(), types)
+            .unwrap_or(original);
+        let method = self
+            .instance_method(sel)
+            .expect("The replaced method should belong to the class");
+
+        Ok(ImplementationGuard {
+            method,
+            original,
+            _marker: PhantomData,
+        })
+    }
+}
+
+/// Check that the signature of a function matches the type encoding of a
+/// method.
+fn check_signature<F: MethodImplementation>(method: Method) -> Result {
+    let types = method.type_encoding();
+    if encode::signature_matches(types, &F::RETURN, &F::ARGUMENTS[2..]) {
+        return Ok(());
+    }
+
+    let mut found = encode::method_type_encoding(&F::RETURN, F::ARGUMENTS);
+    found.pop();
+    Err(Error::mismatched_types(method.name(), types.into(), found))
+}
+
+/// A replaced method implementation.
+///
+/// The original implementation is restored when this guard is dropped.
+/// It is returned by [`Method::replace_implementation`] and
+/// [`Class::replace_method`].
+#[must_use = "the original implementation is restored when the guard is dropped"]
+pub struct ImplementationGuard<F> {
+    method: Method,
+    original: Imp,
+    _marker: PhantomData<F>,
+}
+
+impl<F: MethodImplementation> ImplementationGuard<F> {
+    /// Get the method whose implementation was replaced.
+    pub fn method(&self) -> Method {
+        self.method
+    }
+
+    /// Get the original implementation of the method.
+    pub fn original(&self) -> F {
+        unsafe { F::from_imp(self.original) }
+    }
+}
+
+impl<F> fmt::Debug for ImplementationGuard<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ImplementationGuard")
+            .field("method", &self.method)
+            .finish()
+    }
+}
+
+impl<F> Drop for ImplementationGuard<F> {
+    fn drop(&mut self) {
+        unsafe { ffi::method_setImplementation(self.method.as_ptr(), self.original) };
+    }
+}
+
+/// A pair of methods whose implementations were exchanged.
+///
+/// The implementations are exchanged back when this guard is dropped. It
+/// is returned by [`Method::exchange`].
+#[must_use = "the implementations are exchanged back when the guard is dropped"]
+#[derive(Debug)]
+pub struct ExchangeGuard {
+    first: Method,
+    second: Method,
+}
+
+impl Drop for ExchangeGuard {
+    fn drop(&mut self) {
+        unsafe { ffi::method_exchangeImplementations(self.first.as_ptr(), self.second.as_ptr()) };
+    }
+}
+
+/// Take ownership of a string that was allocated by the runtime.
+unsafe fn copied_string(c_ptr: *mut c_char) -> Option<String> {
+    if c_ptr.is_null() {
+        return None;
+    }
+
+    let string = String::from_utf8_lossy(CStr::from_ptr(c_ptr).to_bytes()).into_owned();
+    libc::free(c_ptr as *mut libc::c_void);
+    Some(string)
+}
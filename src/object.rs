@@ -0,0 +1,105 @@
+// BSL 1.0 License
+
+//! Runtime operations on Objective C objects.
+
+use crate::{ffi, Class, Sel};
+use core::fmt;
+
+opaque_type! {
+    #[doc = "A pointer to an Objective C object."]
+    Object
+}
+
+#[allow(non_camel_case_types)]
+pub type id = Object;
+
+impl fmt::Debug for Object {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Object").field(&self.as_ptr()).finish()
+    }
+}
+
+impl Object {
+    /// Get the class of this object.
+    pub fn class(&self) -> Class {
+        unsafe { Class::from_ptr(ffi::object_getClass(self.as_ptr())) }
+    }
+
+    /// Change the class of this object, returning its previous class.
+    ///
+    /// # Safety
+    ///
+    /// The new class must have the same instance layout as the previous
+    /// one, and must be able to handle any message sent to this object.
+    pub unsafe fn set_class(&self, cls: Class) -> Class {
+        Class::from_ptr(ffi::object_setClass(self.as_ptr(), cls.as_ptr()))
+    }
+
+    /// Tell whether this object responds to the given selector.
+    pub fn responds_to(&self, sel: Sel) -> bool {
+        self.class().responds_to(sel)
+    }
+
+    /// Tell whether this object is an instance of the given class, or of
+    /// one of its subclasses.
+    pub fn is_kind_of(&self, cls: Class) -> bool {
+        let own = self.class();
+        own == cls || own.ancestors().any(|ancestor| ancestor == cls)
+    }
+
+    /// Tell whether this object is an instance of exactly the given class.
+    pub fn is_member_of(&self, cls: Class) -> bool {
+        self.class() == cls
+    }
+
+    /// Create a shallow copy of this object, with `extra_bytes` of
+    /// additional space after its instance variables.
+    ///
+    /// Returns `None` if the copy could not be allocated.
+    ///
+    /// # Safety
+    ///
+    /// The instance variables of the object must be safe to copy
+    /// bitwise. The copy must be destroyed with [`Object::dispose`].
+    pub unsafe fn copy(&self, extra_bytes: usize) -> Option<Object> {
+        #[cfg(not(target_vendor = "apple"))]
+        let ptr = ffi::object_copy(self.as_ptr(), self.class().instance_size() + extra_bytes);
+        #[cfg(target_vendor = "apple")]
+        let ptr = ffi::object_copy(self.as_ptr(), extra_bytes);
+
+        if ptr.is_null() {
+            None
+        } else {
+            Some(Object::from_ptr(ptr))
+        }
+    }
+
+    /// Destroy this object and free its memory, without running any of
+    /// its Objective C `dealloc` methods.
+    ///
+    /// # Safety
+    ///
+    /// The object must not be used after it is disposed.
+    pub unsafe fn dispose(self) {
+        ffi::object_dispose(self.as_ptr());
+    }
+}
+
+impl Class {
+    /// Allocate a new instance of this class, with `extra_bytes` of
+    /// additional space after its instance variables.
+    ///
+    /// The instance is not initialized; this is intended for root classes
+    /// that do not derive from `NSObject`. It should be destroyed with
+    /// [`Object::dispose`].
+    ///
+    /// Returns `None` if the instance could not be allocated.
+    pub fn create_instance(&self, extra_bytes: usize) -> Option<Object> {
+        let ptr = unsafe { ffi::class_createInstance(self.as_ptr(), extra_bytes) };
+        if ptr.is_null() {
+            None
+        } else {
+            Some(unsafe { Object::from_ptr(ptr) })
+        }
+    }
+}
@@ -0,0 +1,242 @@
+// BSL 1.0 License
+
+//! Declared properties of Objective-C classes.
+
+use crate::{
+    cstr::{runtime_str, CStr},
+    ffi, Class, List,
+};
+use core::fmt;
+
+opaque_type! {
+    #[doc = "A property declared by an Objective-C class or protocol."]
+    Property
+}
+
+impl fmt::Debug for Property {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Property")
+            .field("name", &self.name())
+            .field("attributes", &self.attributes())
+            .finish()
+    }
+}
+
+impl Property {
+    /// Get the name of this property.
+    pub fn name(&self) -> &str {
+        let c_ptr = unsafe { ffi::property_getName(self.as_ptr()) };
+        unsafe { runtime_str(c_ptr) }
+    }
+
+    /// Get the raw attribute string of this property, as returned by the
+    /// runtime.
+    pub fn attribute_string(&self) -> &str {
+        let c_ptr = unsafe { ffi::property_getAttributes(self.as_ptr()) };
+        unsafe { runtime_str(c_ptr) }
+    }
+
+    /// Get the parsed attributes of this property.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the runtime returns a malformed attribute string.
+    pub fn attributes(&self) -> PropertyAttributes<'_> {
+        PropertyAttributes::parse(self.attribute_string())
+            .expect("The runtime should return valid property attributes")
+    }
+}
+
+/// How the setter of a property stores its new value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SetterSemantics {
+    /// The value is assigned directly.
+    Assign,
+    /// The value is retained.
+    Retain,
+    /// The value is copied.
+    Copy,
+    /// A weak reference to the value is stored.
+    Weak,
+}
+
+/// The attributes of a property.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PropertyAttributes<'a> {
+    type_encoding: &'a str,
+    readonly: bool,
+    setter_semantics: SetterSemantics,
+    nonatomic: bool,
+    dynamic: bool,
+    getter: Option<&'a str>,
+    setter: Option<&'a str>,
+    ivar: Option<&'a str>,
+}
+
+impl<'a> PropertyAttributes<'a> {
+    /// Parse an attribute string, as returned by `property_getAttributes`.
+    ///
+    /// Returns `None` if the string does not start with the type encoding
+    /// of the property. Unknown attributes are ignored.
+    pub fn parse(attributes: &'a str) -> Option<PropertyAttributes<'a>> {
+        let mut parts = attributes.split(',');
+        let type_encoding = parts.next()?.strip_prefix('T')?;
+
+        let mut parsed = PropertyAttributes {
+            type_encoding,
+            readonly: false,
+            setter_semantics: SetterSemantics::Assign,
+            nonatomic: false,
+            dynamic: false,
+            getter: None,
+            setter: None,
+            ivar: None,
+        };
+
+        for part in parts {
+            let value = part.get(1..).unwrap_or("");
+            match part.chars().next() {
+                Some('R') => parsed.readonly = true,
+                Some('C') => parsed.setter_semantics = SetterSemantics::Copy,
+                Some('&') => parsed.setter_semantics = SetterSemantics::Retain,
+                Some('W') => parsed.setter_semantics = SetterSemantics::Weak,
+                Some('N') => parsed.nonatomic = true,
+                Some('D') => parsed.dynamic = true,
+                Some('G') => parsed.getter = Some(value),
+                Some('S') => parsed.setter = Some(value),
+                Some('V') => parsed.ivar = Some(value),
+                _ => {}
+            }
+        }
+
+        Some(parsed)
+    }
+
+    /// Get the type encoding of the property.
+    pub fn type_encoding(&self) -> &'a str {
+        self.type_encoding
+    }
+
+    /// Tell whether the property is read-only.
+    pub fn is_readonly(&self) -> bool {
+        self.readonly
+    }
+
+    /// Get how the setter of the property stores its new value.
+    pub fn setter_semantics(&self) -> SetterSemantics {
+        self.setter_semantics
+    }
+
+    /// Tell whether the property is non-atomic.
+    pub fn is_nonatomic(&self) -> bool {
+        self.nonatomic
+    }
+
+    /// Tell whether the property is `@dynamic`.
+    pub fn is_dynamic(&self) -> bool {
+        self.dynamic
+    }
+
+    /// Get the name of the custom getter of the property, if it has one.
+    pub fn getter(&self) -> Option<&'a str> {
+        self.getter
+    }
+
+    /// Get the name of the custom setter of the property, if it has one.
+    pub fn setter(&self) -> Option<&'a str> {
+        self.setter
+    }
+
+    /// Get the name of the instance variable backing the property, if it
+    /// has one.
+    pub fn ivar(&self) -> Option<&'a str> {
+        self.ivar
+    }
+}
+
+impl Class {
+    /// Get the property of this class with the given name.
+    ///
+    /// This also searches the superclasses of this class.
+    pub fn property(&self, name: &CStr) -> Option<Property> {
+        let ptr = unsafe { ffi::class_getProperty(self.as_ptr(), name.as_ptr()) };
+        if ptr.is_null() {
+            None
+        } else {
+            Some(unsafe { Property::from_ptr(ptr) })
+        }
+    }
+
+    /// Iterate over the properties declared by this class.
+    ///
+    /// Properties declared by superclasses are not included.
+    pub fn properties(&self) -> List<Property> {
+        let mut count = 0;
+        unsafe {
+            let properties = ffi::class_copyPropertyList(self.as_ptr(), &mut count);
+            List::from_raw(properties as *mut Property, count as usize)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copied_nonatomic_object() {
+        let attributes = PropertyAttributes::parse("T@\"NSString\",C,N,V_name").unwrap();
+        assert_eq!(attributes.type_encoding(), "@\"NSString\"");
+        assert_eq!(attributes.setter_semantics(), SetterSemantics::Copy);
+        assert!(attributes.is_nonatomic());
+        assert!(!attributes.is_readonly());
+        assert!(!attributes.is_dynamic());
+        assert_eq!(attributes.ivar(), Some("_name"));
+        assert_eq!(attributes.getter(), None);
+        assert_eq!(attributes.setter(), None);
+    }
+
+    #[test]
+    fn readonly_with_custom_getter() {
+        let attributes = PropertyAttributes::parse("Ti,R,GisFoo").unwrap();
+        assert_eq!(attributes.type_encoding(), "i");
+        assert!(attributes.is_readonly());
+        assert_eq!(attributes.getter(), Some("isFoo"));
+        assert_eq!(attributes.setter_semantics(), SetterSemantics::Assign);
+        assert!(!attributes.is_nonatomic());
+        assert_eq!(attributes.ivar(), None);
+    }
+
+    #[test]
+    fn retained_dynamic_struct() {
+        let attributes =
+            PropertyAttributes::parse("T{CGRect={CGPoint=dd}{CGSize=dd}},&,D").unwrap();
+        assert_eq!(
+            attributes.type_encoding(),
+            "{CGRect={CGPoint=dd}{CGSize=dd}}"
+        );
+        assert_eq!(attributes.setter_semantics(), SetterSemantics::Retain);
+        assert!(attributes.is_dynamic());
+    }
+
+    #[test]
+    fn weak_with_custom_setter() {
+        let attributes = PropertyAttributes::parse("T@,W,SsetDelegate:,V_delegate").unwrap();
+        assert_eq!(attributes.setter_semantics(), SetterSemantics::Weak);
+        assert_eq!(attributes.setter(), Some("setDelegate:"));
+        assert_eq!(attributes.ivar(), Some("_delegate"));
+    }
+
+    #[test]
+    fn unknown_attributes_are_ignored() {
+        let attributes = PropertyAttributes::parse("Tq,N,P,?").unwrap();
+        assert_eq!(attributes.type_encoding(), "q");
+        assert!(attributes.is_nonatomic());
+    }
+
+    #[test]
+    fn missing_type_is_malformed() {
+        assert_eq!(PropertyAttributes::parse("C,N,V_name"), None);
+        assert_eq!(PropertyAttributes::parse(""), None);
+    }
+}
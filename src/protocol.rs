@@ -0,0 +1,149 @@
+// BSL 1.0 License
+
+//! Types for representing the `Protocol` construct in Objective C.
+
+use crate::{
+    cstr::{c_char, CStr},
+    ffi, Bool, Class, List, Sel,
+};
+use core::{fmt, iter, ptr::NonNull, str};
+
+opaque_type! {
+    #[doc = "An Objective-C protocol."]
+    Protocol, AtomicProtocol
+}
+
+impl fmt::Debug for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Protocol").field(&self.name()).finish()
+    }
+}
+
+impl Protocol {
+    /// Tries to get a protocol from a C string.
+    pub fn new(name: &CStr) -> Option<Protocol> {
+        let ptr = unsafe { ffi::objc_getProtocol(name.as_ptr()) };
+        Some(Protocol {
+            ptr: NonNull::new(ptr as _)?,
+        })
+    }
+
+    /// Get the name of this protocol.
+    pub fn name(&self) -> &str {
+        let c_ptr = unsafe { ffi::protocol_getName(self.ptr.as_ptr()) };
+        let c_str = unsafe { CStr::from_ptr(c_ptr) };
+
+        str::from_utf8(c_str.to_bytes()).expect("Protocol names should be valid UTF-8")
+    }
+
+    /// Tell whether this protocol conforms to another protocol.
+    pub fn conforms_to(&self, other: Protocol) -> bool {
+        unsafe { ffi::protocol_conformsToProtocol(self.as_ptr(), other.as_ptr()) }.as_bool()
+    }
+
+    /// Iterate over the protocols adopted by this protocol.
+    pub fn adopted_protocols(&self) -> List<Protocol> {
+        let mut count = 0;
+        unsafe {
+            let protocols = ffi::protocol_copyProtocolList(self.as_ptr(), &mut count);
+            List::from_raw(protocols as *mut Protocol, count as usize)
+        }
+    }
+
+    /// Get the description of a method declared by this protocol.
+    ///
+    /// `required` and `instance` select whether to look for a required or
+    /// optional method, and an instance or class method.
+    pub fn method_description(
+        &self,
+        sel: Sel,
+        required: bool,
+        instance: bool,
+    ) -> Option<MethodDescription> {
+        let desc = unsafe {
+            ffi::protocol_getMethodDescription(
+                self.as_ptr(),
+                sel.as_ptr(),
+                Bool::new(required),
+                Bool::new(instance),
+            )
+        };
+
+        if desc.name.is_null() || desc.types.is_null() {
+            None
+        } else {
+            Some(desc)
+        }
+    }
+
+    /// Iterate over the descriptions of the methods declared by this
+    /// protocol.
+    ///
+    /// `required` and `instance` select whether to list the required or
+    /// optional methods, and the instance or class methods.
+    pub fn method_descriptions(&self, required: bool, instance: bool) -> List<MethodDescription> {
+        let mut count = 0;
+        unsafe {
+            let descs = ffi::protocol_copyMethodDescriptionList(
+                self.as_ptr(),
+                Bool::new(required),
+                Bool::new(instance),
+                &mut count,
+            );
+            List::from_raw(descs, count as usize)
+        }
+    }
+}
+
+/// The description of a method declared by a protocol.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct MethodDescription {
+    name: *const (),
+    types: *const c_char,
+}
+
+impl fmt::Debug for MethodDescription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MethodDescription")
+            .field("name", &self.name())
+            .field("types", &self.types())
+            .finish()
+    }
+}
+
+impl MethodDescription {
+    /// Get the selector of the method.
+    pub fn name(&self) -> Sel {
+        unsafe { Sel::from_ptr(self.name) }
+    }
+
+    /// Get the type encoding of the method.
+    pub fn types(&self) -> &str {
+        let c_str = unsafe { CStr::from_ptr(self.types) };
+
+        str::from_utf8(c_str.to_bytes()).expect("Type encodings should be valid UTF-8")
+    }
+}
+
+impl Class {
+    /// Tell whether this class, or any of its superclasses, conforms to
+    /// the given protocol.
+    pub fn conforms_to(&self, protocol: Protocol) -> bool {
+        // not all runtimes check the superclasses, so walk them here
+        iter::once(*self).chain(self.ancestors()).any(|cls| {
+            unsafe { ffi::class_conformsToProtocol(cls.as_ptr(), protocol.as_ptr()) }.as_bool()
+        })
+    }
+
+    /// Iterate over the protocols adopted by this class.
+    ///
+    /// Protocols adopted by superclasses are not included.
+    pub fn adopted_protocols(&self) -> List<Protocol> {
+        let mut count = 0;
+        unsafe {
+            let protocols = ffi::class_copyProtocolList(self.as_ptr(), &mut count);
+            List::from_raw(protocols as *mut Protocol, count as usize)
+        }
+    }
+}
@@ -0,0 +1,228 @@
+// BSL 1.0 License
+
+//! Reference-counted pointers to Objective C objects.
+
+use crate::{ffi, MessageTarget, Object};
+use alloc::boxed::Box;
+use core::{
+    cell::UnsafeCell,
+    cmp, fmt,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    mem,
+    ops::Deref,
+    ptr,
+};
+
+/// A type that is a transparent wrapper around an [`Object`].
+///
+/// This can be implemented for types that represent a specific kind of
+/// object, so that they can be used with [`Id`].
+///
+/// # Safety
+///
+/// The type must be `#[repr(transparent)]` around an [`Object`].
+pub unsafe trait ObjectType: Copy + 'static {
+    /// Get the object that this type wraps.
+    fn as_object(self) -> Object {
+        unsafe { mem::transmute_copy(&self) }
+    }
+
+    /// Wrap an object in this type.
+    ///
+    /// # Safety
+    ///
+    /// The object must be a valid instance of this type.
+    unsafe fn from_object(obj: Object) -> Self {
+        mem::transmute_copy(&obj)
+    }
+}
+
+unsafe impl ObjectType for Object {}
+
+/// An owning pointer to an Objective C object.
+///
+/// The object is retained when the `Id` is cloned, and released when it
+/// is dropped.
+///
+/// When an `Id` or an `Option<Id>` is used as the return type of a message,
+/// the returned object is retained, unless the selector belongs to the
+/// `alloc`, `new`, `copy`, `mutableCopy` or `init` families, whose methods
+/// already return retained objects.
+#[repr(transparent)]
+pub struct Id<T: ObjectType = Object> {
+    obj: T,
+}
+
+impl<T: ObjectType> Id<T> {
+    /// Create an `Id` from an object that has already been retained,
+    /// such as the result of `alloc`, `new` or `copy`.
+    ///
+    /// # Safety
+    ///
+    /// The object must have a retain count of +1 that is owned by the
+    /// caller, and must be a valid instance of `T`.
+    pub unsafe fn from_retained(obj: T) -> Id<T> {
+        Id { obj }
+    }
+
+    /// Create an `Id` from an object that is not owned by the caller,
+    /// retaining it in the process.
+    ///
+    /// # Safety
+    ///
+    /// The object must be a valid instance of `T`.
+    pub unsafe fn retain(obj: T) -> Id<T> {
+        ffi::objc_retain(obj.as_object().as_ptr());
+        Id { obj }
+    }
+
+    /// Get the pointer associated with this object.
+    pub fn as_ptr(&self) -> *const () {
+        self.obj.as_object().as_ptr()
+    }
+
+    /// Create a weak reference to this object.
+    pub fn downgrade(this: &Id<T>) -> WeakId<T> {
+        WeakId::new(this)
+    }
+
+    /// Consume this `Id` without releasing the object.
+    ///
+    /// The caller becomes responsible for the +1 retain count.
+    pub fn into_raw(this: Id<T>) -> T {
+        let obj = this.obj;
+        mem::forget(this);
+        obj
+    }
+}
+
+impl<T: ObjectType> Deref for Id<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.obj
+    }
+}
+
+impl<T: ObjectType> Clone for Id<T> {
+    fn clone(&self) -> Self {
+        unsafe { Id::retain(self.obj) }
+    }
+}
+
+impl<T: ObjectType> Drop for Id<T> {
+    fn drop(&mut self) {
+        unsafe { ffi::objc_release(self.as_ptr()) };
+    }
+}
+
+impl<T: ObjectType> fmt::Debug for Id<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Id").field(&self.as_ptr()).finish()
+    }
+}
+
+impl<T: ObjectType> PartialEq for Id<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_ptr() == other.as_ptr()
+    }
+}
+
+impl<T: ObjectType> Eq for Id<T> {}
+
+impl<T: ObjectType> PartialOrd for Id<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: ObjectType> Ord for Id<T> {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.as_ptr().cmp(&other.as_ptr())
+    }
+}
+
+impl<T: ObjectType> Hash for Id<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_ptr().hash(state)
+    }
+}
+
+unsafe impl<T: ObjectType> MessageTarget for &Id<T> {
+    fn ptr(self) -> *mut () {
+        self.as_ptr() as _
+    }
+}
+
+/// A zeroing weak reference to an Objective C object.
+///
+/// The reference does not keep the object alive, and is cleared by the
+/// runtime when the object is deallocated.
+///
+/// The runtime keeps track of where each weak reference is stored, so the
+/// reference is boxed to give it a stable address. This requires a global
+/// allocator, even when the crate is used without `std`.
+pub struct WeakId<T: ObjectType = Object> {
+    // the runtime keeps track of the address of the weak reference, so
+    // it needs to stay in the same place in memory
+    slot: Box<UnsafeCell<*const ()>>,
+    _marker: PhantomData<T>,
+}
+
+impl<T: ObjectType> WeakId<T> {
+    /// Create a weak reference to the given object.
+    pub fn new(obj: &Id<T>) -> WeakId<T> {
+        let weak = WeakId::default();
+        unsafe { ffi::objc_initWeak(weak.slot.get(), obj.as_ptr()) };
+        weak
+    }
+
+    /// Try to get a strong reference to the object.
+    ///
+    /// Returns `None` if the object has been deallocated.
+    pub fn upgrade(&self) -> Option<Id<T>> {
+        let ptr = unsafe { ffi::objc_loadWeakRetained(self.slot.get()) };
+        if ptr.is_null() {
+            None
+        } else {
+            Some(unsafe { Id::from_retained(T::from_object(Object::from_ptr(ptr))) })
+        }
+    }
+
+    /// Change the object that this weak reference points to.
+    pub fn set(&mut self, obj: Option<&Id<T>>) {
+        let ptr = obj.map_or(ptr::null(), |obj| obj.as_ptr());
+        unsafe { ffi::objc_storeWeak(self.slot.get(), ptr) };
+    }
+}
+
+impl<T: ObjectType> Default for WeakId<T> {
+    /// Create a weak reference that does not point to any object.
+    fn default() -> Self {
+        WeakId {
+            slot: Box::new(UnsafeCell::new(ptr::null())),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: ObjectType> Clone for WeakId<T> {
+    fn clone(&self) -> Self {
+        let weak = WeakId::default();
+        unsafe { ffi::objc_copyWeak(weak.slot.get(), self.slot.get()) };
+        weak
+    }
+}
+
+impl<T: ObjectType> Drop for WeakId<T> {
+    fn drop(&mut self) {
+        unsafe { ffi::objc_destroyWeak(self.slot.get()) };
+    }
+}
+
+impl<T: ObjectType> fmt::Debug for WeakId<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("WeakId(..)")
+    }
+}
@@ -10,6 +10,10 @@ opaque_type! {
     Sel, AtomicSel
 }
 
+// selectors are registered for the lifetime of the process, and never change
+unsafe impl Send for Sel {}
+unsafe impl Sync for Sel {}
+
 impl fmt::Debug for Sel {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_tuple("Sel").field(&self.name()).finish()
@@ -0,0 +1,367 @@
+// BSL 1.0 License
+
+//! Mock objects and spies for testing code that sends Objective C
+//! messages.
+//!
+//! A [`Mock`] creates a new class at runtime, with a method for each
+//! expected selector. Its [`object`](Mock::object) can be used with
+//! `msg_send!` like any other object.
+//!
+//! The mock's class is a subclass of `NSObject` when it is available, so
+//! that the object can be retained and released. Otherwise, it is a root
+//! class whose `retain`, `release` and `autorelease` methods do nothing.
+//!
+//! Messages with selectors that were not expected are recorded through
+//! forwarding, and return zero in the integer return register. Integer,
+//! `BOOL` and pointer returns are therefore zero or nil, but other return
+//! types, such as floats and structures, are unspecified. On Apple
+//! platforms, these messages are forwarded through `-forwardInvocation:`,
+//! which requires Foundation to be loaded.
+//!
+//! A [`Spy`] wraps an existing object, recording the messages that are
+//! sent to it.
+
+use crate::{cstr::CStr, ffi, remove_block_imp, Class, ClassDecl, Encode, Object, RcBlock, Sel};
+use alloc::{format, rc::Rc, string::String, sync::Arc, vec::Vec};
+use core::{
+    cell::Cell,
+    fmt,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use std::sync::Mutex;
+
+mod spy;
+pub use spy::{Message, RecordArguments, Spy, Value};
+
+/// A closure that can implement an expected method of a [`Mock`].
+///
+/// This is implemented for closures taking up to eight arguments, where
+/// `Args` is a tuple of the closure's argument types. The receiver and the
+/// selector are not passed to the closure, which may be called on any
+/// thread that messages the mock.
+pub trait MockMethod<Args>: Send + Sync + 'static {
+    /// Install the closure as the method for `sel` on `cls`, incrementing
+    /// `calls` every time it is called.
+    #[doc(hidden)]
+    unsafe fn install(self, cls: Class, sel: Sel, calls: Arc<AtomicUsize>);
+}
+
+macro_rules! mock_method {
+    ($($ident: ident)*) => {
+        #[allow(non_snake_case)]
+        impl<Func, Ret, $($ident),*> MockMethod<($($ident,)*)> for Func
+        where
+            Func: Fn($($ident),*) -> Ret + Send + Sync + 'static,
+            Ret: Encode + 'static,
+            $($ident: Encode + 'static),*
+        {
+            unsafe fn install(self, cls: Class, sel: Sel, calls: Arc<AtomicUsize>) {
+                let block = RcBlock::new(move |_this: Object, $($ident: $ident),*| -> Ret {
+                    calls.fetch_add(1, Ordering::Relaxed);
+                    self($($ident),*)
+                });
+                cls.set_block_method(sel, &block);
+            }
+        }
+    };
+}
+
+macro_rules! mock_methods {
+    () => {
+        mock_method! {}
+    };
+    ($head: ident $($ident: ident)*) => {
+        mock_method! { $head $($ident)* }
+        mock_methods! { $($ident)* }
+    };
+}
+
+mock_methods! {
+    A B C D E F G H
+}
+
+/// A mock Objective C object.
+///
+/// The object and its class are destroyed when the mock is dropped.
+pub struct Mock {
+    cls: Class,
+    obj: Object,
+    expectations: Vec<ExpectationState>,
+    unexpected: Unexpected,
+}
+
+struct ExpectationState {
+    sel: Sel,
+    calls: Arc<AtomicUsize>,
+    times: Rc<Cell<Option<usize>>>,
+}
+
+impl Mock {
+    /// Create a new mock object without any expected selectors.
+    pub fn new() -> Mock {
+        let mut decl = match optional_class!(NSObject) {
+            Some(ns_object) => unique_class("LoafMock", Some(ns_object)),
+            None => {
+                let mut decl = unique_class("LoafMock", None);
+                add_memory_management(&mut decl);
+                decl
+            }
+        };
+
+        let unexpected: Unexpected = Arc::new(Mutex::new(Vec::new()));
+        let recorded = unexpected.clone();
+        let record = RcBlock::new(move |_this: Object, sel: Sel| {
+            recorded.lock().unwrap().push(sel);
+        });
+        unsafe { decl.add_block_method(sel!(loafMockUnexpected:), &record) };
+        forward_unexpected(&mut decl);
+
+        let cls = decl.register();
+        let obj = cls
+            .create_instance(0)
+            .expect("Failed to allocate the mock object");
+
+        Mock {
+            cls,
+            obj,
+            expectations: Vec::new(),
+            unexpected,
+        }
+    }
+
+    /// Get the mock object.
+    ///
+    /// The object must not be used after the mock is dropped.
+    pub fn object(&self) -> Object {
+        self.obj
+    }
+
+    /// Get the class of the mock object.
+    pub fn class(&self) -> Class {
+        self.cls
+    }
+
+    /// Expect messages with the given selector, which are handled by
+    /// `method`.
+    ///
+    /// Any previous expectation for the selector is replaced.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of arguments taken by `method` does not match
+    /// the selector.
+    pub fn expect<Args, F: MockMethod<Args>>(&mut self, sel: Sel, method: F) -> Expectation {
+        self.expectations
+            .retain(|expectation| expectation.sel != sel);
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let times = Rc::new(Cell::new(None));
+        unsafe { method.install(self.cls, sel, calls.clone()) };
+
+        self.expectations.push(ExpectationState {
+            sel,
+            calls,
+            times: times.clone(),
+        });
+        Expectation { times }
+    }
+
+    /// Expect messages with the given selector, which takes no arguments,
+    /// and return a copy of `value` for each of them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the selector takes arguments.
+    pub fn expect_value<R>(&mut self, sel: Sel, value: R) -> Expectation
+    where
+        R: Encode + Clone + Send + Sync + 'static,
+    {
+        self.expect(sel, move || value.clone())
+    }
+
+    /// Get the number of times that an expected selector was received.
+    ///
+    /// Returns zero if the selector was not expected.
+    pub fn calls(&self, sel: Sel) -> usize {
+        self.expectations
+            .iter()
+            .find(|expectation| expectation.sel == sel)
+            .map_or(0, |expectation| expectation.calls.load(Ordering::Relaxed))
+    }
+
+    /// Get the selectors that were received without being expected.
+    pub fn unexpected(&self) -> Vec<Sel> {
+        self.unexpected.lock().unwrap().clone()
+    }
+
+    /// Check that every expectation was met, and that no unexpected
+    /// selectors were received.
+    ///
+    /// # Panics
+    ///
+    /// Panics with a report of every problem that was found.
+    pub fn verify(&self) {
+        let mut report = String::new();
+
+        for expectation in &self.expectations {
+            if let Some(times) = expectation.times.get() {
+                let calls = expectation.calls.load(Ordering::Relaxed);
+                if calls != times {
+                    report += &format!(
+                        "\n- `{}` was expected {} times, but was received {} times",
+                        expectation.sel.name(),
+                        times,
+                        calls
+                    );
+                }
+            }
+        }
+
+        for sel in self.unexpected.lock().unwrap().iter() {
+            report += &format!("\n- `{}` was received, but was not expected", sel.name());
+        }
+
+        if !report.is_empty() {
+            panic!("Mock expectations were not met:{}", report);
+        }
+    }
+}
+
+impl Default for Mock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Mock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Mock")
+            .field("class", &self.cls)
+            .field("object", &self.obj)
+            .finish()
+    }
+}
+
+impl Drop for Mock {
+    fn drop(&mut self) {
+        unsafe {
+            self.obj.dispose();
+            dispose_class(self.cls);
+        }
+    }
+}
+
+/// An expectation for a selector received by a [`Mock`].
+#[derive(Debug)]
+pub struct Expectation {
+    times: Rc<Cell<Option<usize>>>,
+}
+
+impl Expectation {
+    /// Expect the selector to be received exactly `times` times by the time
+    /// [`Mock::verify`] is called.
+    pub fn times(self, times: usize) -> Expectation {
+        self.times.set(Some(times));
+        self
+    }
+
+    /// Expect the selector to never be received.
+    pub fn never(self) -> Expectation {
+        self.times(0)
+    }
+}
+
+/// Start declaring a class with a unique name starting with `prefix`, or a
+/// root class if there is no superclass.
+fn unique_class(prefix: &str, superclass: Option<Class>) -> ClassDecl {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let id = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let name = format!("{}{}\0", prefix, id);
+    let name = CStr::from_bytes_with_nul(name.as_bytes())
+        .expect("Class names should not contain nul bytes");
+    match superclass {
+        Some(superclass) => ClassDecl::new(name, superclass),
+        None => ClassDecl::root(name),
+    }
+    .expect("Class names should be unique")
+}
+
+/// Dispose of a class that was created for testing, releasing the blocks
+/// that implement its methods.
+///
+/// # Safety
+///
+/// The class must not have any instances or subclasses left.
+unsafe fn dispose_class(cls: Class) {
+    for method in cls.instance_methods().chain(cls.class_methods()) {
+        remove_block_imp(method.implementation());
+    }
+    ffi::objc_disposeClassPair(cls.as_ptr());
+}
+
+/// Add `retain`, `release` and `autorelease` methods that do nothing to a
+/// root class, so that its instances can be used with [`Id`](crate::Id).
+fn add_memory_management(decl: &mut ClassDecl) {
+    extern "C" fn retain(this: Object, _sel: Sel) -> Object {
+        this
+    }
+
+    extern "C" fn release(_this: Object, _sel: Sel) {}
+
+    unsafe {
+        decl.add_method(sel!(retain), retain as extern "C" fn(Object, Sel) -> Object);
+        decl.add_method(sel!(release), release as extern "C" fn(Object, Sel));
+        decl.add_method(
+            sel!(autorelease),
+            retain as extern "C" fn(Object, Sel) -> Object,
+        );
+    }
+}
+
+type Unexpected = Arc<Mutex<Vec<Sel>>>;
+
+/// Forward unexpected selectors to an implementation that records them
+/// with `-loafMockUnexpected:`, and returns zero.
+#[cfg(not(target_vendor = "apple"))]
+fn forward_unexpected(decl: &mut ClassDecl) {
+    use crate::{Forward, MethodImplementation};
+
+    type UnexpectedImp = extern "C" fn(Object, Sel) -> usize;
+
+    extern "C" fn unexpected_message(this: Object, sel: Sel) -> usize {
+        let _ = unsafe { msg_send![() => this, loafMockUnexpected: sel] };
+        0
+    }
+
+    let imp = (unexpected_message as UnexpectedImp).imp();
+    decl.add_forwarding_handler(move |_, _| Forward::Implementation(imp));
+}
+
+/// Forward unexpected selectors to `-forwardInvocation:`, which records
+/// them with `-loafMockUnexpected:`, and leaves the return value zeroed.
+#[cfg(target_vendor = "apple")]
+fn forward_unexpected(decl: &mut ClassDecl) {
+    use crate::cstr::c_char;
+
+    let signature = RcBlock::new(|this: Object, sel: Sel| -> Option<Object> {
+        let types = match this.class().instance_method(sel) {
+            Some(method) => unsafe { ffi::method_getTypeEncoding(method.as_ptr()) },
+            // the arguments are ignored, and the return value is zero
+            None => b"Q@:\0".as_ptr() as *const c_char,
+        };
+
+        let cls = optional_class!(NSMethodSignature)?;
+        unsafe { msg_send![Option<Object> => cls, signatureWithObjCTypes: types] }
+            .ok()
+            .flatten()
+    });
+    unsafe { decl.add_block_method(sel!(methodSignatureForSelector:), &signature) };
+
+    let forward = RcBlock::new(|this: Object, invocation: Object| {
+        if let Ok(sel) = unsafe { msg_send![Sel => invocation, selector] } {
+            let _ = unsafe { msg_send![() => this, loafMockUnexpected: sel] };
+        }
+    });
+    unsafe { decl.add_block_method(sel!(forwardInvocation:), &forward) };
+}
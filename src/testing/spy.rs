@@ -0,0 +1,603 @@
+// BSL 1.0 License
+
+//! Spies that record the messages sent to an object.
+
+use super::{dispose_class, unique_class};
+use crate::{
+    cstr::{c_char, runtime_str},
+    encode::{self, EncodeArguments},
+    ffi, send_message, Class, Encode, Encoding, Id, Imp, MessageArguments, Object, RcBlock, Result,
+    Sel,
+};
+use alloc::{
+    string::{String, ToString},
+    sync::Arc,
+    vec,
+    vec::Vec,
+};
+use core::{any::Any, ffi::c_void, fmt, mem, slice};
+use std::sync::Mutex;
+
+/// A value passed to or returned from a message, decoded by its type
+/// encoding.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// A signed integer.
+    Int(i64),
+    /// An unsigned integer.
+    UInt(u64),
+    /// A floating point number.
+    Float(f64),
+    /// A C `bool`.
+    Bool(bool),
+    /// No value.
+    Void,
+    /// An object, or nil.
+    ///
+    /// The object is retained, so that it can still be inspected after the
+    /// message returns.
+    Object(Option<Id>),
+    /// A class, or nil.
+    Class(Option<Class>),
+    /// A selector, or null.
+    Sel(Option<Sel>),
+    /// Any other pointer, including C strings and blocks.
+    Pointer(*const ()),
+    /// A value that could not be decoded, such as a struct, with its type
+    /// encoding.
+    Unknown(String),
+}
+
+// objects are retained, and retain counts are atomic, so they stay valid on
+// any thread; classes and selectors are already `Send`, and the remaining
+// pointers are only addresses, which are unsafe to dereference anyway
+unsafe impl Send for Value {}
+
+impl Value {
+    /// Decode a value by its type encoding.
+    pub fn of<T: Encode>(value: &T) -> Value {
+        // only scalars are read as bytes, since other types may have padding
+        let is_scalar = !matches!(
+            T::ENCODING,
+            Encoding::Array(..)
+                | Encoding::Struct(..)
+                | Encoding::Union(..)
+                | Encoding::BitField(_)
+                | Encoding::Unknown
+        );
+
+        let decoded = if is_scalar {
+            let bytes = unsafe {
+                slice::from_raw_parts(value as *const T as *const u8, mem::size_of::<T>())
+            };
+            Value::decode(&T::ENCODING, bytes)
+        } else {
+            None
+        };
+
+        decoded.unwrap_or_else(|| Value::Unknown(T::ENCODING.to_string()))
+    }
+
+    /// Decode a value from its bytes, using a type encoding returned by
+    /// the runtime.
+    fn decode_str(types: &str, bytes: &[u8]) -> Value {
+        const SCALARS: &[Encoding<'static>] = &[
+            Encoding::Char,
+            Encoding::Short,
+            Encoding::Int,
+            Encoding::Long,
+            Encoding::LongLong,
+            Encoding::UChar,
+            Encoding::UShort,
+            Encoding::UInt,
+            Encoding::ULong,
+            Encoding::ULongLong,
+            Encoding::Float,
+            Encoding::Double,
+            Encoding::Bool,
+            Encoding::Void,
+            Encoding::Object,
+            Encoding::Block,
+            Encoding::Class,
+            Encoding::Sel,
+            Encoding::String,
+        ];
+
+        let encoding = if encode::strip_qualifiers(types).starts_with('^') {
+            Some(&Encoding::Pointer(&Encoding::Unknown))
+        } else {
+            SCALARS
+                .iter()
+                .find(|encoding| encoding.equivalent_to_str(types))
+        };
+
+        encoding
+            .and_then(|encoding| Value::decode(encoding, bytes.get(..scalar_size(encoding)?)?))
+            .unwrap_or_else(|| Value::Unknown(types.to_string()))
+    }
+
+    fn decode(encoding: &Encoding<'_>, bytes: &[u8]) -> Option<Value> {
+        let value = match encoding {
+            Encoding::Char
+            | Encoding::Short
+            | Encoding::Int
+            | Encoding::Long
+            | Encoding::LongLong => Value::Int(match bytes.len() {
+                1 => i8::from_ne_bytes(bytes.try_into().ok()?).into(),
+                2 => i16::from_ne_bytes(bytes.try_into().ok()?).into(),
+                4 => i32::from_ne_bytes(bytes.try_into().ok()?).into(),
+                8 => i64::from_ne_bytes(bytes.try_into().ok()?),
+                _ => return None,
+            }),
+            Encoding::UChar
+            | Encoding::UShort
+            | Encoding::UInt
+            | Encoding::ULong
+            | Encoding::ULongLong => Value::UInt(match bytes.len() {
+                1 => u8::from_ne_bytes(bytes.try_into().ok()?).into(),
+                2 => u16::from_ne_bytes(bytes.try_into().ok()?).into(),
+                4 => u32::from_ne_bytes(bytes.try_into().ok()?).into(),
+                8 => u64::from_ne_bytes(bytes.try_into().ok()?),
+                _ => return None,
+            }),
+            Encoding::Float => Value::Float(f32::from_ne_bytes(bytes.try_into().ok()?).into()),
+            Encoding::Double => Value::Float(f64::from_ne_bytes(bytes.try_into().ok()?)),
+            Encoding::Bool => Value::Bool(bytes.iter().any(|&byte| byte != 0)),
+            Encoding::Void => Value::Void,
+            Encoding::Object => {
+                let object = read_pointer(bytes)?;
+                Value::Object(object.map(|ptr| unsafe { Id::retain(Object::from_ptr(ptr)) }))
+            }
+            Encoding::Class => {
+                Value::Class(read_pointer(bytes)?.map(|ptr| unsafe { Class::from_ptr(ptr) }))
+            }
+            Encoding::Sel => {
+                Value::Sel(read_pointer(bytes)?.map(|ptr| unsafe { Sel::from_ptr(ptr) }))
+            }
+            Encoding::String | Encoding::Block | Encoding::Pointer(_) => {
+                Value::Pointer(read_pointer(bytes)?.unwrap_or(core::ptr::null()))
+            }
+            _ => return None,
+        };
+
+        Some(value)
+    }
+}
+
+/// Get the size of a scalar type.
+fn scalar_size(encoding: &Encoding<'_>) -> Option<usize> {
+    let size = match encoding {
+        Encoding::Char | Encoding::UChar | Encoding::Bool => 1,
+        Encoding::Short | Encoding::UShort => 2,
+        Encoding::Int | Encoding::UInt | Encoding::Float => 4,
+        Encoding::Long | Encoding::ULong => mem::size_of::<libc::c_long>(),
+        Encoding::LongLong | Encoding::ULongLong | Encoding::Double => 8,
+        Encoding::Void => 0,
+        Encoding::Object
+        | Encoding::Block
+        | Encoding::Class
+        | Encoding::Sel
+        | Encoding::String
+        | Encoding::Pointer(_) => mem::size_of::<*const ()>(),
+        _ => return None,
+    };
+
+    Some(size)
+}
+
+/// Read a pointer from its bytes, returning `Some(None)` for null.
+fn read_pointer(bytes: &[u8]) -> Option<Option<*const ()>> {
+    let address = usize::from_ne_bytes(bytes.try_into().ok()?);
+    Some((address != 0).then_some(address as *const ()))
+}
+
+/// Arguments whose values can be recorded by a [`Spy`].
+///
+/// This is implemented for tuples of up to twenty-six [`Encode`] types.
+pub trait RecordArguments: EncodeArguments {
+    /// Decode each of the arguments.
+    fn values(&self) -> Vec<Value>;
+}
+
+macro_rules! record_arguments {
+    ($($ident: ident)*) => {
+        #[allow(non_snake_case)]
+        impl<$($ident: Encode),*> RecordArguments for ($($ident,)*) {
+            fn values(&self) -> Vec<Value> {
+                let ($($ident,)*) = self;
+                vec![$(Value::of($ident)),*]
+            }
+        }
+    };
+}
+
+macro_rules! record_arguments_all {
+    () => {
+        record_arguments! {}
+    };
+    ($head: ident $($ident: ident)*) => {
+        record_arguments! { $head $($ident)* }
+        record_arguments_all! { $($ident)* }
+    };
+}
+
+record_arguments_all! {
+    A B C D E F G H I J K L M N O P Q R S T U V W X Y Z
+}
+
+/// A message that was recorded by a [`Spy`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    /// The selector of the message.
+    pub sel: Sel,
+    /// The arguments of the message, not including the receiver and the
+    /// selector.
+    pub arguments: Vec<Value>,
+    /// The value returned by the message.
+    ///
+    /// This is `None` if the message raised an exception.
+    pub result: Option<Value>,
+    /// The exception raised by the message, if any.
+    pub exception: Option<Id>,
+}
+
+// the exception is retained like the objects in `Value`
+unsafe impl Send for Message {}
+
+/// A spy that records the messages sent to an object.
+///
+/// The spy has a proxy [`object`](Spy::object), which can be passed to
+/// Objective C code in place of the original object. Every message sent to
+/// the proxy is forwarded to the original object as a full invocation,
+/// through `-forwardInvocation:`, and is recorded along with its arguments
+/// and return value. The arguments are decoded using the type encoding of
+/// the original object's method. An exception raised by the original object
+/// is recorded, then raised again from the proxy. Messages can also be sent
+/// from Rust with [`Spy::send`].
+///
+/// Forwarding messages sent to the proxy requires `NSInvocation`, so the
+/// proxy only responds to them if Foundation is loaded. [`Spy::send`] works
+/// without it.
+///
+/// The proxy is destroyed when the spy is dropped.
+pub struct Spy {
+    original: Object,
+    cls: Class,
+    proxy: Object,
+    messages: Arc<Mutex<Vec<Message>>>,
+}
+
+impl Spy {
+    /// Create a spy for the given object.
+    ///
+    /// # Safety
+    ///
+    /// The object must stay valid for as long as the spy is used.
+    pub unsafe fn new(original: Object) -> Spy {
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let mut decl = unique_class("LoafSpy", None);
+
+        let signature = RcBlock::new(move |_proxy: Object, sel: Sel| -> Option<Object> {
+            method_signature(original, sel)
+        });
+        decl.add_block_method(sel!(methodSignatureForSelector:), &signature);
+
+        let recorded = messages.clone();
+        let forward = RcBlock::new(
+            move |_proxy: Object, invocation: Object| -> Option<Object> {
+                let message = forward_invocation(original, invocation)?;
+                let exception = message.exception.as_deref().copied();
+                recorded.lock().unwrap().push(message);
+                exception
+            },
+        );
+        decl.add_block_method(sel!(loafSpyForwardInvocation:), &forward);
+
+        let cls = decl.register();
+        add_forward_invocation(cls);
+        let proxy = cls
+            .create_instance(0)
+            .expect("Failed to allocate the spy object");
+
+        Spy {
+            original,
+            cls,
+            proxy,
+            messages,
+        }
+    }
+
+    /// Get the proxy object, which forwards every message to the original
+    /// object.
+    ///
+    /// The object must not be used after the spy is dropped.
+    pub fn object(&self) -> Object {
+        self.proxy
+    }
+
+    /// Get the original object.
+    pub fn original(&self) -> Object {
+        self.original
+    }
+
+    /// Send a message to the original object, recording it along with its
+    /// arguments and return value.
+    ///
+    /// # Safety
+    ///
+    /// The message must be valid for the original object.
+    pub unsafe fn send<Return, Arguments>(&self, sel: Sel, arguments: Arguments) -> Result<Return>
+    where
+        Return: Any + Encode,
+        Arguments: MessageArguments + RecordArguments,
+    {
+        let values = arguments.values();
+        let result = send_message(self.original, sel, arguments, true);
+
+        self.messages.lock().unwrap().push(Message {
+            sel,
+            arguments: values,
+            result: result.as_ref().ok().map(Value::of),
+            exception: result
+                .as_ref()
+                .err()
+                .and_then(|error| error.exception().cloned()),
+        });
+        result
+    }
+
+    /// Get the messages that were recorded, in the order they were sent.
+    pub fn messages(&self) -> Vec<Message> {
+        self.messages.lock().unwrap().clone()
+    }
+
+    /// Forget the messages that were recorded.
+    pub fn clear(&self) {
+        self.messages.lock().unwrap().clear();
+    }
+}
+
+impl fmt::Debug for Spy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Spy")
+            .field("original", &self.original)
+            .field("object", &self.proxy)
+            .finish()
+    }
+}
+
+impl Drop for Spy {
+    fn drop(&mut self) {
+        unsafe {
+            self.proxy.dispose();
+            dispose_class(self.cls);
+        }
+    }
+}
+
+/// Build the method signature for a message forwarded to `original`, from
+/// the type encoding of its method.
+///
+/// Returns `None` if Foundation is not loaded, so that the message is not
+/// forwarded.
+unsafe fn method_signature(original: Object, sel: Sel) -> Option<Object> {
+    let signature_class = optional_class!(NSMethodSignature)?;
+    match original.class().instance_method(sel) {
+        Some(method) => {
+            let types = ffi::method_getTypeEncoding(method.as_ptr());
+            msg_send![Option<Object> => signature_class, signatureWithObjCTypes: types]
+                .ok()
+                .flatten()
+        }
+        // the original may forward the message itself
+        None => msg_send![Option<Object> => original, methodSignatureForSelector: sel]
+            .ok()
+            .flatten(),
+    }
+}
+
+/// Add `-forwardInvocation:`, which forwards the invocation with
+/// `-loafSpyForwardInvocation:` and raises the exception it returns.
+///
+/// The exception is raised from a plain function, since it cannot unwind
+/// through a block.
+unsafe fn add_forward_invocation(cls: Class) {
+    extern "C-unwind" fn forward_and_raise(this: Object, _sel: Sel, invocation: Object) {
+        let exception =
+            unsafe { msg_send![Option<Object> => this, loafSpyForwardInvocation: invocation] };
+        if let Ok(Some(exception)) = exception {
+            unsafe { ffi::objc_exception_throw(exception.as_ptr()) }
+        }
+    }
+
+    let imp = forward_and_raise as extern "C-unwind" fn(Object, Sel, Object);
+    let types = encode::method_type_encoding(
+        &Encoding::Void,
+        &[Object::ENCODING, Sel::ENCODING, Object::ENCODING],
+    );
+    ffi::class_addMethod(
+        cls.as_ptr(),
+        sel!(forwardInvocation:).as_ptr(),
+        mem::transmute::<extern "C-unwind" fn(Object, Sel, Object), Imp>(imp),
+        types.as_ptr() as *const c_char,
+    );
+}
+
+/// Invoke a forwarded invocation on `original`, and record it.
+///
+/// Exceptions raised by the original method are caught, since they cannot
+/// unwind through the block, and recorded on the message. The return value
+/// is then left zeroed.
+unsafe fn forward_invocation(original: Object, invocation: Object) -> Option<Message> {
+    let sel = msg_send![Sel => invocation, selector].ok()?;
+    let signature = msg_send![Object => invocation, methodSignature].ok()?;
+    let count = msg_send![usize => signature, numberOfArguments].ok()?;
+    let frame_length = msg_send![usize => signature, frameLength].ok()?;
+    let return_length = msg_send![usize => signature, methodReturnLength].ok()?;
+
+    // the buffer is large enough for any one argument, and the return value
+    let mut buffer = vec![0u8; frame_length.max(return_length)];
+    let mut arguments = Vec::with_capacity(count.saturating_sub(2));
+    for index in 2..count {
+        let types = msg_send![*const c_char => signature, getArgumentTypeAtIndex: index].ok()?;
+        let () = msg_send![
+            invocation,
+            getArgument: buffer.as_mut_ptr() as *mut c_void
+            atIndex: index as isize
+        ]
+        .ok()?;
+        arguments.push(Value::decode_str(runtime_str(types), &buffer));
+    }
+
+    let (result, exception) = match msg_send![() => invocation, invokeWithTarget: original] {
+        Ok(()) => {
+            let result = (|| {
+                let types = msg_send![*const c_char => signature, methodReturnType].ok()?;
+                if return_length > 0 {
+                    buffer.fill(0);
+                    let () = msg_send![
+                        invocation,
+                        getReturnValue: buffer.as_mut_ptr() as *mut c_void
+                    ]
+                    .ok()?;
+                }
+                Some(Value::decode_str(runtime_str(types), &buffer))
+            })();
+            (result, None)
+        }
+        Err(error) => (None, error.exception().cloned()),
+    };
+
+    Some(Message {
+        sel,
+        arguments,
+        result,
+        exception,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_integers() {
+        assert_eq!(
+            Value::decode(&Encoding::Int, &(-5i32).to_ne_bytes()),
+            Some(Value::Int(-5))
+        );
+        assert_eq!(
+            Value::decode(&Encoding::Char, &[0xff]),
+            Some(Value::Int(-1))
+        );
+        assert_eq!(
+            Value::decode(&Encoding::UChar, &[0xff]),
+            Some(Value::UInt(255))
+        );
+        assert_eq!(
+            Value::decode(&Encoding::ULongLong, &u64::MAX.to_ne_bytes()),
+            Some(Value::UInt(u64::MAX))
+        );
+        assert_eq!(Value::decode(&Encoding::Int, &[0, 0, 0]), None);
+    }
+
+    #[test]
+    fn decode_floats_and_bools() {
+        assert_eq!(
+            Value::decode(&Encoding::Float, &1.5f32.to_ne_bytes()),
+            Some(Value::Float(1.5))
+        );
+        assert_eq!(
+            Value::decode(&Encoding::Double, &(-0.25f64).to_ne_bytes()),
+            Some(Value::Float(-0.25))
+        );
+        assert_eq!(
+            Value::decode(&Encoding::Bool, &[1]),
+            Some(Value::Bool(true))
+        );
+        assert_eq!(
+            Value::decode(&Encoding::Bool, &[0]),
+            Some(Value::Bool(false))
+        );
+        assert_eq!(Value::decode(&Encoding::Void, &[]), Some(Value::Void));
+    }
+
+    #[test]
+    fn decode_pointers() {
+        let null = 0usize.to_ne_bytes();
+        let address = 0x1000usize.to_ne_bytes();
+
+        assert_eq!(
+            Value::decode(&Encoding::Object, &null),
+            Some(Value::Object(None))
+        );
+        assert_eq!(
+            Value::decode(&Encoding::Class, &address),
+            Some(Value::Class(Some(unsafe {
+                Class::from_ptr(0x1000 as *const ())
+            })))
+        );
+        assert_eq!(
+            Value::decode(&Encoding::String, &address),
+            Some(Value::Pointer(0x1000 as *const ()))
+        );
+        assert_eq!(
+            Value::decode(&Encoding::Pointer(&Encoding::Int), &null),
+            Some(Value::Pointer(core::ptr::null()))
+        );
+    }
+
+    #[test]
+    fn decode_unsupported_encodings() {
+        const POINT: Encoding<'static> =
+            Encoding::Struct("CGPoint", &[Encoding::Double, Encoding::Double]);
+
+        assert_eq!(Value::decode(&POINT, &[0; 16]), None);
+        assert_eq!(Value::decode(&Encoding::Unknown, &[]), None);
+    }
+
+    #[test]
+    fn decode_runtime_type_encodings() {
+        assert_eq!(Value::decode_str("i", &7i32.to_ne_bytes()), Value::Int(7));
+        assert_eq!(Value::decode_str("v", &[0; 8]), Value::Void);
+        assert_eq!(
+            Value::decode_str("@\"NSString\"", &0usize.to_ne_bytes()),
+            Value::Object(None)
+        );
+        assert_eq!(
+            Value::decode_str("r*", &0x1000usize.to_ne_bytes()),
+            Value::Pointer(0x1000 as *const ())
+        );
+        assert_eq!(
+            Value::decode_str("^{CGPoint=dd}", &0x1000usize.to_ne_bytes()),
+            Value::Pointer(0x1000 as *const ())
+        );
+        assert_eq!(
+            Value::decode_str("{CGPoint=dd}", &[0; 16]),
+            Value::Unknown("{CGPoint=dd}".to_string())
+        );
+    }
+
+    #[test]
+    fn decode_runtime_type_encodings_from_larger_buffers() {
+        // arguments are read into a buffer sized for the whole frame
+        let mut buffer = [0u8; 16];
+        buffer[..2].copy_from_slice(&(-3i16).to_ne_bytes());
+        assert_eq!(Value::decode_str("s", &buffer), Value::Int(-3));
+
+        assert_eq!(
+            Value::decode_str("q", &[0; 4]),
+            Value::Unknown("q".to_string())
+        );
+    }
+
+    #[test]
+    fn value_of() {
+        assert_eq!(Value::of(&42u16), Value::UInt(42));
+        assert_eq!(Value::of(&()), Value::Void);
+        assert_eq!(
+            Value::of(&[1i32, 2]),
+            Value::Unknown(<[i32; 2]>::ENCODING.to_string())
+        );
+    }
+}
@@ -0,0 +1,193 @@
+// BSL 1.0 License
+
+//! Tests for forwarding messages from runtime-created classes.
+
+#![allow(deprecated)]
+
+use cstr_core::cstr;
+use loafjective_c::{class, msg_send, sel, Class, ClassDecl, Forward, Object, Sel};
+use std::{
+    ptr,
+    sync::{
+        atomic::{AtomicPtr, Ordering},
+        Once,
+    },
+};
+
+// unrecognized selectors are forwarded through Foundation on Apple platforms
+#[cfg(target_vendor = "apple")]
+#[link(name = "Foundation", kind = "framework")]
+extern "C" {}
+
+/// The object that messages are forwarded to.
+static TARGET: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+
+extern "C" fn target_value(_this: Object, _sel: Sel) -> i32 {
+    7
+}
+
+extern "C" fn target_add(_this: Object, _sel: Sel, a: i32, b: i32) -> i32 {
+    a + b
+}
+
+#[cfg(not(target_vendor = "apple"))]
+extern "C" fn forwarded_value(_this: Object, _sel: Sel) -> i32 {
+    5
+}
+
+/// Decide how the forwarding class forwards a message.
+fn forward(_receiver: Object, sel: Sel) -> Forward {
+    if sel == sel!(value) || sel == sel!(add:to:) {
+        let target = TARGET.load(Ordering::Acquire);
+        return Forward::Target(unsafe { Object::from_ptr(target) });
+    }
+
+    #[cfg(not(target_vendor = "apple"))]
+    if sel == sel!(implementedValue) {
+        use loafjective_c::MethodImplementation;
+
+        let imp = forwarded_value as extern "C" fn(Object, Sel) -> i32;
+        return Forward::Implementation(imp.imp());
+    }
+
+    Forward::Unhandled
+}
+
+/// Declare a target class, a class that forwards to an instance of it, and
+/// a class without a forwarding handler.
+fn declare_classes() {
+    static DECLARE: Once = Once::new();
+
+    DECLARE.call_once(|| unsafe {
+        // the hook must be installed before the forwarding handler, so
+        // that it is the previous hook
+        #[cfg(not(target_vendor = "apple"))]
+        previous_hook::install();
+
+        let mut target = ClassDecl::root(cstr!("LoafForwardTestTarget")).unwrap();
+        target.add_method(
+            sel!(value),
+            target_value as extern "C" fn(Object, Sel) -> i32,
+        );
+        target.add_method(
+            sel!(add:to:),
+            target_add as extern "C" fn(Object, Sel, i32, i32) -> i32,
+        );
+        let target = target.register().create_instance(0).unwrap();
+        TARGET.store(target.as_ptr() as *mut (), Ordering::Release);
+
+        let mut forwarder = ClassDecl::root(cstr!("LoafForwardTestForwarder")).unwrap();
+        forwarder.add_forwarding_handler(forward);
+        forwarder.register();
+
+        ClassDecl::root(cstr!("LoafForwardTestPlain"))
+            .unwrap()
+            .register();
+    });
+}
+
+/// A GNUstep forwarding hook installed before the crate's, which the crate's
+/// hook must fall back on.
+#[cfg(not(target_vendor = "apple"))]
+mod previous_hook {
+    use super::*;
+    use loafjective_c::{Imp, MethodImplementation};
+    use std::mem;
+
+    type MessageForward = unsafe extern "C" fn(*const (), *const ()) -> Option<Imp>;
+
+    #[link(name = "objc", kind = "dylib")]
+    extern "C" {
+        static mut __objc_msg_forward2: Option<MessageForward>;
+    }
+
+    static PREVIOUS: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+
+    extern "C" fn chained_value(_this: Object, _sel: Sel) -> i32 {
+        3
+    }
+
+    unsafe extern "C" fn message_forward(receiver: *const (), sel: *const ()) -> Option<Imp> {
+        if Sel::from_ptr(sel) == sel!(chainedValue) {
+            let imp = chained_value as extern "C" fn(Object, Sel) -> i32;
+            return Some(imp.imp());
+        }
+
+        let previous = PREVIOUS.load(Ordering::Acquire);
+        if previous.is_null() {
+            None
+        } else {
+            let previous: MessageForward = mem::transmute(previous);
+            previous(receiver, sel)
+        }
+    }
+
+    pub(super) unsafe fn install() {
+        let previous = ptr::addr_of_mut!(__objc_msg_forward2).replace(Some(message_forward));
+        PREVIOUS.store(
+            previous.map_or(ptr::null_mut(), |f| f as *mut ()),
+            Ordering::Release,
+        );
+    }
+}
+
+/// Run a closure with a new instance of the given class.
+fn with_instance<R>(cls: Class, f: impl FnOnce(Object) -> R) -> R {
+    let obj = cls.create_instance(0).unwrap();
+    let result = f(obj);
+    unsafe { obj.dispose() };
+    result
+}
+
+#[test]
+fn forward_to_target() {
+    declare_classes();
+
+    let value = with_instance(class!(LoafForwardTestForwarder), |obj| unsafe {
+        msg_send![i32 => obj, value]
+    });
+    assert_eq!(value.unwrap(), 7);
+}
+
+#[test]
+fn forward_to_target_with_arguments() {
+    declare_classes();
+
+    let sum = with_instance(class!(LoafForwardTestForwarder), |obj| unsafe {
+        msg_send![i32 => obj, add: 2i32 to: 3i32]
+    });
+    assert_eq!(sum.unwrap(), 5);
+}
+
+#[test]
+#[cfg(not(target_vendor = "apple"))]
+fn forward_to_implementation() {
+    declare_classes();
+
+    let value = with_instance(class!(LoafForwardTestForwarder), |obj| unsafe {
+        msg_send![i32 => obj, implementedValue]
+    });
+    assert_eq!(value.unwrap(), 5);
+}
+
+#[test]
+#[cfg(not(target_vendor = "apple"))]
+fn unhandled_messages_use_the_previous_hook() {
+    declare_classes();
+
+    let value = with_instance(class!(LoafForwardTestForwarder), |obj| unsafe {
+        msg_send![i32 => obj, chainedValue]
+    });
+    assert_eq!(value.unwrap(), 3);
+}
+
+#[test]
+#[cfg(not(target_vendor = "apple"))]
+fn other_classes_use_the_previous_hook() {
+    declare_classes();
+
+    let value = with_instance(class!(LoafForwardTestPlain), |obj| unsafe {
+        msg_send![i32 => obj, chainedValue]
+    });
+    assert_eq!(value.unwrap(), 3);
+}
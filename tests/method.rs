@@ -0,0 +1,179 @@
+// BSL 1.0 License
+
+//! Tests for replacing and exchanging method implementations.
+
+#![allow(deprecated)]
+
+use cstr_core::cstr;
+use loafjective_c::{class, sel, send_message, Class, ClassDecl, Imp, Object, Sel};
+use std::sync::Once;
+
+type ValueImp = extern "C" fn(Object, Sel) -> i32;
+
+extern "C" fn one(_this: Object, _sel: Sel) -> i32 {
+    1
+}
+
+extern "C" fn two(_this: Object, _sel: Sel) -> i32 {
+    2
+}
+
+extern "C" fn three(_this: Object, _sel: Sel) -> i32 {
+    3
+}
+
+extern "C" fn add(_this: Object, _sel: Sel, a: i32, b: i32) -> i32 {
+    a + b
+}
+
+/// Declare the classes used by the tests, each of which is only changed by
+/// a single test.
+fn declare_classes() {
+    static DECLARE: Once = Once::new();
+
+    DECLARE.call_once(|| unsafe {
+        let mut replaced = ClassDecl::root(cstr!("LoafMethodTestReplaced")).unwrap();
+        replaced.add_method(sel!(value), one as ValueImp);
+        replaced.register();
+
+        let mut parent = ClassDecl::root(cstr!("LoafMethodTestParent")).unwrap();
+        parent.add_method(sel!(value), one as ValueImp);
+        let parent = parent.register();
+        ClassDecl::new(cstr!("LoafMethodTestChild"), parent)
+            .unwrap()
+            .register();
+
+        let mut exchanged = ClassDecl::root(cstr!("LoafMethodTestExchanged")).unwrap();
+        exchanged.add_method(sel!(first), one as ValueImp);
+        exchanged.add_method(sel!(second), two as ValueImp);
+        exchanged.add_method(
+            sel!(add:to:),
+            add as extern "C" fn(Object, Sel, i32, i32) -> i32,
+        );
+        exchanged.register();
+    });
+}
+
+/// Run a closure with a new instance of the given class.
+fn with_instance<R>(cls: Class, f: impl FnOnce(Object) -> R) -> R {
+    let obj = cls.create_instance(0).unwrap();
+    let result = f(obj);
+    unsafe { obj.dispose() };
+    result
+}
+
+/// Send `sel` to a new instance of the given class.
+fn value_of(cls: Class, sel: Sel) -> i32 {
+    with_instance(cls, |obj| unsafe {
+        send_message::<_, i32, _>(obj, sel, (), true)
+    })
+    .unwrap()
+}
+
+fn same_imp(a: Imp, b: Imp) -> bool {
+    a as usize == b as usize
+}
+
+#[test]
+fn implementation_guard_restores_original() {
+    declare_classes();
+    let cls = class!(LoafMethodTestReplaced);
+    let method = cls.instance_method(sel!(value)).unwrap();
+    let original = method.implementation();
+
+    {
+        let guard = unsafe {
+            method.replace_implementation(|original: ValueImp| {
+                with_instance(cls, |obj| assert_eq!(original(obj, sel!(value)), 1));
+                two as ValueImp
+            })
+        }
+        .unwrap();
+
+        assert_eq!(value_of(cls, sel!(value)), 2);
+        assert_eq!(
+            with_instance(cls, |obj| guard.original()(obj, sel!(value))),
+            1
+        );
+    }
+
+    assert_eq!(value_of(cls, sel!(value)), 1);
+    assert!(same_imp(method.implementation(), original));
+}
+
+#[test]
+fn replace_implementation_checks_the_signature() {
+    declare_classes();
+    let method = class!(LoafMethodTestReplaced)
+        .instance_method(sel!(value))
+        .unwrap();
+
+    let replaced = unsafe {
+        method.replace_implementation(|_: extern "C" fn(Object, Sel) -> f64| {
+            unreachable!("the replacement should not be called")
+        })
+    };
+    assert!(replaced.is_err());
+}
+
+#[test]
+fn replace_inherited_method_adds_an_override() {
+    declare_classes();
+    let parent = class!(LoafMethodTestParent);
+    let child = class!(LoafMethodTestChild);
+    let parent_method = parent.instance_method(sel!(value)).unwrap();
+    let parent_imp = parent_method.implementation();
+
+    {
+        let guard =
+            unsafe { child.replace_method(sel!(value), |_: ValueImp| three as ValueImp) }.unwrap();
+        assert_ne!(guard.method(), parent_method);
+
+        assert_eq!(value_of(child, sel!(value)), 3);
+        assert_eq!(value_of(parent, sel!(value)), 1);
+        assert!(same_imp(parent_method.implementation(), parent_imp));
+    }
+
+    // the added method falls back on the inherited implementation
+    assert_eq!(value_of(child, sel!(value)), 1);
+    assert_eq!(value_of(parent, sel!(value)), 1);
+    assert!(same_imp(parent_method.implementation(), parent_imp));
+}
+
+#[test]
+fn exchanging_twice_round_trips() {
+    declare_classes();
+    let cls = class!(LoafMethodTestExchanged);
+    let first = cls.instance_method(sel!(first)).unwrap();
+    let second = cls.instance_method(sel!(second)).unwrap();
+
+    unsafe {
+        first.exchange_implementations(second);
+        assert_eq!(value_of(cls, sel!(first)), 2);
+        assert_eq!(value_of(cls, sel!(second)), 1);
+
+        first.exchange_implementations(second);
+        assert_eq!(value_of(cls, sel!(first)), 1);
+        assert_eq!(value_of(cls, sel!(second)), 2);
+    }
+
+    {
+        let _guard = unsafe { first.exchange(second) }.unwrap();
+        assert_eq!(value_of(cls, sel!(first)), 2);
+        assert_eq!(value_of(cls, sel!(second)), 1);
+    }
+
+    assert_eq!(value_of(cls, sel!(first)), 1);
+    assert_eq!(value_of(cls, sel!(second)), 2);
+}
+
+#[test]
+fn exchange_rejects_different_signatures() {
+    declare_classes();
+    let cls = class!(LoafMethodTestExchanged);
+    let first = cls.instance_method(sel!(first)).unwrap();
+    let add = cls.instance_method(sel!(add:to:)).unwrap();
+
+    assert!(unsafe { first.exchange(add) }.is_err());
+    assert_eq!(value_of(cls, sel!(first)), 1);
+}
@@ -0,0 +1,82 @@
+// BSL 1.0 License
+
+//! Tests for mock objects.
+
+#![cfg(feature = "testing")]
+#![allow(deprecated)]
+
+use loafjective_c::{msg_send, sel, testing::Mock, Id};
+use std::panic::{self, AssertUnwindSafe};
+
+// unexpected selectors are forwarded through Foundation on Apple platforms
+#[cfg(target_vendor = "apple")]
+#[link(name = "Foundation", kind = "framework")]
+extern "C" {}
+
+/// Run `Mock::verify`, returning its report if it panics.
+fn verify_report(mock: &Mock) -> Option<String> {
+    let result = panic::catch_unwind(AssertUnwindSafe(|| mock.verify()));
+    result
+        .err()
+        .map(|payload| match payload.downcast::<String>() {
+            Ok(report) => *report,
+            Err(_) => panic!("Mock::verify should panic with a String"),
+        })
+}
+
+#[test]
+fn expected_call_count() {
+    let mut mock = Mock::new();
+    let _ = mock.expect_value(sel!(value), 42i32).times(2);
+    let obj = mock.object();
+
+    for _ in 0..2 {
+        let value = unsafe { msg_send![i32 => obj, value] }.unwrap();
+        assert_eq!(value, 42);
+    }
+
+    assert_eq!(mock.calls(sel!(value)), 2);
+    assert_eq!(verify_report(&mock), None);
+}
+
+#[test]
+fn expected_call_count_not_met() {
+    let mut mock = Mock::new();
+    let _ = mock.expect(sel!(add:to:), |a: i32, b: i32| a + b).times(2);
+    let obj = mock.object();
+
+    let sum = unsafe { msg_send![i32 => obj, add: 2i32 to: 3i32] }.unwrap();
+    assert_eq!(sum, 5);
+
+    let report = verify_report(&mock).expect("the expectation should not be met");
+    assert!(report.contains("`add:to:` was expected 2 times, but was received 1 times"));
+}
+
+#[test]
+fn unexpected_selector_is_reported() {
+    let mock = Mock::new();
+    let obj = mock.object();
+
+    // the forwarded messages must keep working for any signature
+    for _ in 0..2 {
+        let value = unsafe { msg_send![i32 => obj, unexpectedWith: 7i32] }.unwrap();
+        assert_eq!(value, 0);
+    }
+
+    assert!(mock.unexpected().contains(&sel!(unexpectedWith:)));
+    let report = verify_report(&mock).expect("the unexpected selector should be reported");
+    assert!(report.contains("`unexpectedWith:` was received, but was not expected"));
+}
+
+#[test]
+fn memory_management_is_not_unexpected() {
+    let mock = Mock::new();
+
+    let obj = unsafe { Id::retain(mock.object()) };
+    let other = obj.clone();
+    drop(obj);
+    drop(other);
+
+    assert!(mock.unexpected().is_empty());
+    assert_eq!(verify_report(&mock), None);
+}
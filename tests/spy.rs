@@ -0,0 +1,126 @@
+// BSL 1.0 License
+
+//! Tests for spies.
+
+#![cfg(feature = "testing")]
+#![allow(deprecated)]
+
+use loafjective_c::{
+    msg_send, optional_class, sel,
+    testing::{Message, Mock, Spy, Value},
+    Id, Object,
+};
+
+// the proxy forwards messages through Foundation's `NSInvocation`
+#[cfg(target_vendor = "apple")]
+#[link(name = "Foundation", kind = "framework")]
+extern "C" {}
+
+/// Create an empty `NSArray`, if Foundation is loaded.
+fn empty_array() -> Option<Id> {
+    let cls = optional_class!(NSArray)?;
+    let array = unsafe { msg_send![Object => cls, new] }.ok()?;
+    Some(unsafe { Id::from_retained(array) })
+}
+
+#[test]
+fn send_records_messages() {
+    let mut mock = Mock::new();
+    let _ = mock.expect(sel!(add:to:), |a: i32, b: i32| a + b);
+    let _ = mock.expect_value(sel!(isReady), true);
+
+    let spy = unsafe { Spy::new(mock.object()) };
+    let sum: i32 = unsafe { spy.send(sel!(add:to:), (2i32, 3i32)) }.unwrap();
+    assert_eq!(sum, 5);
+    let ready: bool = unsafe { spy.send(sel!(isReady), ()) }.unwrap();
+    assert!(ready);
+
+    assert_eq!(
+        spy.messages(),
+        [
+            Message {
+                sel: sel!(add:to:),
+                arguments: vec![Value::Int(2), Value::Int(3)],
+                result: Some(Value::Int(5)),
+                exception: None,
+            },
+            Message {
+                sel: sel!(isReady),
+                arguments: vec![],
+                result: Some(Value::Bool(true)),
+                exception: None,
+            },
+        ]
+    );
+    assert_eq!(mock.calls(sel!(add:to:)), 1);
+
+    spy.clear();
+    assert!(spy.messages().is_empty());
+}
+
+#[test]
+fn send_records_exceptions() {
+    let array = match empty_array() {
+        Some(array) => array,
+        None => return,
+    };
+
+    let spy = unsafe { Spy::new(*array) };
+    let result: Result<Option<Object>, _> = unsafe { spy.send(sel!(objectAtIndex:), (5usize,)) };
+    let error = result.unwrap_err();
+
+    let messages = spy.messages();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].sel, sel!(objectAtIndex:));
+    assert_eq!(messages[0].arguments, [Value::UInt(5)]);
+    assert_eq!(messages[0].result, None);
+    assert_eq!(messages[0].exception.as_ref(), error.exception());
+    assert!(messages[0].exception.is_some());
+}
+
+#[test]
+fn proxy_records_forwarded_messages() {
+    if optional_class!(NSInvocation).is_none() {
+        return;
+    }
+
+    let mut mock = Mock::new();
+    let _ = mock.expect(sel!(add:to:), |a: i32, b: i32| a + b);
+
+    let spy = unsafe { Spy::new(mock.object()) };
+    let sum = unsafe { msg_send![i32 => spy.object(), add: 2i32 to: 3i32] }.unwrap();
+    assert_eq!(sum, 5);
+
+    assert_eq!(
+        spy.messages(),
+        [Message {
+            sel: sel!(add:to:),
+            arguments: vec![Value::Int(2), Value::Int(3)],
+            result: Some(Value::Int(5)),
+            exception: None,
+        }]
+    );
+    assert_eq!(mock.calls(sel!(add:to:)), 1);
+}
+
+#[test]
+fn proxy_raises_exceptions_again() {
+    if optional_class!(NSInvocation).is_none() {
+        return;
+    }
+    let array = match empty_array() {
+        Some(array) => array,
+        None => return,
+    };
+
+    let spy = unsafe { Spy::new(*array) };
+    let result = unsafe { msg_send![Option<Object> => spy.object(), objectAtIndex: 5usize] };
+    let error = result.unwrap_err();
+    assert!(error.exception().is_some());
+
+    let messages = spy.messages();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].arguments, [Value::UInt(5)]);
+    assert_eq!(messages[0].result, None);
+    assert_eq!(messages[0].exception.as_ref(), error.exception());
+}
@@ -0,0 +1,119 @@
+// BSL 1.0 License
+
+//! Tests for sending messages to the superclass of runtime-created classes.
+
+#![allow(deprecated)]
+
+use cstr_core::cstr;
+use loafjective_c::{class, msg_send, sel, Class, ClassDecl, Object, Sel};
+use std::sync::Once;
+
+extern "C" fn parent_value(_this: Object, _sel: Sel) -> i32 {
+    1
+}
+
+extern "C" fn parent_class_value(_this: Class, _sel: Sel) -> i32 {
+    10
+}
+
+extern "C" fn parent_add(_this: Object, _sel: Sel, a: i32, b: i32) -> i32 {
+    a + b
+}
+
+extern "C" fn child_value(this: Object, _sel: Sel) -> i32 {
+    let parent = class!(LoafSuperTestParent);
+    let value: i32 = unsafe { msg_send![super(this, parent), value] }.unwrap();
+    value + 41
+}
+
+extern "C" fn child_class_value(this: Class, _sel: Sel) -> i32 {
+    let parent = class!(LoafSuperTestParent);
+    let value: i32 = unsafe { msg_send![super(this, parent), classValue] }.unwrap();
+    value + 5
+}
+
+extern "C" fn child_add(this: Object, _sel: Sel, a: i32, b: i32) -> i32 {
+    let parent = class!(LoafSuperTestParent);
+    unsafe { msg_send![i32 => super(this, parent), add: a to: b] }.unwrap() * 2
+}
+
+/// Declare a root class and a subclass that overrides its methods.
+fn declare_classes() {
+    static DECLARE: Once = Once::new();
+
+    DECLARE.call_once(|| unsafe {
+        let mut parent = ClassDecl::root(cstr!("LoafSuperTestParent")).unwrap();
+        parent.add_method(
+            sel!(value),
+            parent_value as extern "C" fn(Object, Sel) -> i32,
+        );
+        parent.add_method(
+            sel!(add:to:),
+            parent_add as extern "C" fn(Object, Sel, i32, i32) -> i32,
+        );
+        parent.add_class_method(
+            sel!(classValue),
+            parent_class_value as extern "C" fn(Class, Sel) -> i32,
+        );
+        let parent = parent.register();
+
+        let mut child = ClassDecl::new(cstr!("LoafSuperTestChild"), parent).unwrap();
+        child.add_method(
+            sel!(value),
+            child_value as extern "C" fn(Object, Sel) -> i32,
+        );
+        child.add_method(
+            sel!(add:to:),
+            child_add as extern "C" fn(Object, Sel, i32, i32) -> i32,
+        );
+        child.add_class_method(
+            sel!(classValue),
+            child_class_value as extern "C" fn(Class, Sel) -> i32,
+        );
+        child.register();
+    });
+}
+
+/// Run a closure with a new instance of the given class.
+fn with_instance<R>(cls: Class, f: impl FnOnce(Object) -> R) -> R {
+    let obj = cls.create_instance(0).unwrap();
+    let result = f(obj);
+    unsafe { obj.dispose() };
+    result
+}
+
+#[test]
+fn super_instance_method() {
+    declare_classes();
+
+    let parent = with_instance(class!(LoafSuperTestParent), |obj| unsafe {
+        msg_send![i32 => obj, value]
+    });
+    assert_eq!(parent.unwrap(), 1);
+
+    let child = with_instance(class!(LoafSuperTestChild), |obj| unsafe {
+        msg_send![i32 => obj, value]
+    });
+    assert_eq!(child.unwrap(), 42);
+}
+
+#[test]
+fn super_instance_method_with_arguments() {
+    declare_classes();
+
+    let child = with_instance(class!(LoafSuperTestChild), |obj| unsafe {
+        msg_send![i32 => obj, add: 2i32 to: 3i32]
+    });
+    assert_eq!(child.unwrap(), 10);
+}
+
+#[test]
+fn super_class_method() {
+    declare_classes();
+
+    let parent: i32 = unsafe { msg_send![class!(LoafSuperTestParent), classValue] }.unwrap();
+    assert_eq!(parent, 10);
+
+    let child: i32 = unsafe { msg_send![class!(LoafSuperTestChild), classValue] }.unwrap();
+    assert_eq!(child, 15);
+}
@@ -0,0 +1,59 @@
+// BSL 1.0 License
+
+//! Tests for zeroing weak references.
+
+#![allow(deprecated)]
+
+use loafjective_c::{msg_send, optional_class, Id, Object, WeakId};
+
+/// Create a new `NSObject`, if the class is available.
+fn new_object() -> Option<Id> {
+    let cls = optional_class!(NSObject)?;
+    let obj = unsafe { msg_send![Object => cls, new] }.unwrap();
+    Some(unsafe { Id::from_retained(obj) })
+}
+
+#[test]
+fn upgrade_while_strong_reference_lives() {
+    let obj = match new_object() {
+        Some(obj) => obj,
+        None => return,
+    };
+    let weak = Id::downgrade(&obj);
+
+    assert_eq!(weak.upgrade(), Some(obj.clone()));
+    assert_eq!(weak.clone().upgrade(), Some(obj));
+}
+
+#[test]
+fn upgrade_after_last_strong_reference_is_dropped() {
+    let obj = match new_object() {
+        Some(obj) => obj,
+        None => return,
+    };
+    let weak = Id::downgrade(&obj);
+    let other = obj.clone();
+
+    drop(obj);
+    assert!(weak.upgrade().is_some());
+
+    drop(other);
+    assert!(weak.upgrade().is_none());
+}
+
+#[test]
+fn set_changes_the_referenced_object() {
+    let obj = match new_object() {
+        Some(obj) => obj,
+        None => return,
+    };
+
+    let mut weak = WeakId::default();
+    assert!(weak.upgrade().is_none());
+
+    weak.set(Some(&obj));
+    assert_eq!(weak.upgrade(), Some(obj));
+
+    weak.set(None);
+    assert!(weak.upgrade().is_none());
+}